@@ -0,0 +1,338 @@
+use std::{
+    collections::HashMap,
+    f64::consts::LN_10,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+use crate::{
+    node::Node,
+    token::{FuncType, TokenType},
+};
+
+use super::{Interpreter, InterpreterError, InterpreterResult};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dual {
+    pub re: f64,
+    pub eps: f64,
+}
+impl Dual {
+    pub fn new(re: f64, eps: f64) -> Self {
+        Self { re, eps }
+    }
+
+    pub fn constant(re: f64) -> Self {
+        Self::new(re, 0f64)
+    }
+}
+impl Add for Dual {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.re + rhs.re, self.eps + rhs.eps)
+    }
+}
+impl Sub for Dual {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.re - rhs.re, self.eps - rhs.eps)
+    }
+}
+impl Neg for Dual {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.re, -self.eps)
+    }
+}
+impl Mul for Dual {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(self.re * rhs.re, self.eps * rhs.re + self.re * rhs.eps)
+    }
+}
+impl Div for Dual {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        Self::new(
+            self.re / rhs.re,
+            (self.eps * rhs.re - self.re * rhs.eps) / (rhs.re * rhs.re),
+        )
+    }
+}
+
+#[derive(Default)]
+struct Tape {
+    values: Vec<f64>,
+    parents: Vec<Vec<(usize, f64)>>,
+}
+impl Tape {
+    fn push(&mut self, value: f64, parents: Vec<(usize, f64)>) -> usize {
+        self.values.push(value);
+        self.parents.push(parents);
+        self.values.len() - 1
+    }
+
+    fn adjoints(&self, output: usize) -> Vec<f64> {
+        let mut adjoints = vec![0f64; self.values.len()];
+        adjoints[output] = 1f64;
+        for i in (0..=output).rev() {
+            for (parent, weight) in &self.parents[i] {
+                adjoints[*parent] += weight * adjoints[i];
+            }
+        }
+        adjoints
+    }
+}
+
+fn slope(func: &FuncType, x: f64, value: f64) -> f64 {
+    match func {
+        FuncType::Sin => x.cos(),
+        FuncType::Cos => -x.sin(),
+        FuncType::Tan => 1f64 / x.cos().powi(2),
+        FuncType::Csc => -value / x.tan(),
+        FuncType::Sec => value * x.tan(),
+        FuncType::Cot => -1f64 / x.sin().powi(2),
+        FuncType::Ln => 1f64 / x,
+        FuncType::Log => 1f64 / (x * LN_10),
+        FuncType::Sqrt => 0.5 / value,
+    }
+}
+
+impl Interpreter {
+    fn lookup(&self, var: char, map: &HashMap<char, f64>) -> InterpreterResult<f64> {
+        map.get(&var)
+            .or_else(|| self.table.get(&var))
+            .copied()
+            .ok_or_else(|| InterpreterError::SolveError(String::from("Not substituted")))
+    }
+
+    fn apply(&self, func: &FuncType, x: f64) -> InterpreterResult<f64> {
+        match self.visit_func(func, &Node::Num(x), None)? {
+            Node::Num(value) => Ok(value),
+            _ => unreachable!(),
+        }
+    }
+
+    fn power(&self, base: f64, exponent: f64) -> InterpreterResult<f64> {
+        match self.visit_exponent(&Node::Num(base), &Node::Num(exponent), None)? {
+            Node::Num(value) => Ok(value),
+            _ => Err(InterpreterError::Undefined),
+        }
+    }
+
+    fn dual(&self, node: &Node, var: char, map: &HashMap<char, f64>) -> InterpreterResult<Dual> {
+        Ok(match node {
+            Node::Num(n) => Dual::constant(*n),
+            Node::Var(v) if *v == var => Dual::new(self.lookup(*v, map)?, 1f64),
+            Node::Var(v) => Dual::constant(self.lookup(*v, map)?),
+            Node::Func { func, arg } => {
+                let arg = self.dual(arg, var, map)?;
+                let value = self.apply(func, arg.re)?;
+                Dual::new(value, slope(func, arg.re, value) * arg.eps)
+            }
+            Node::Exponent { base, exponent } => {
+                let (b, e) = (self.dual(base, var, map)?, self.dual(exponent, var, map)?);
+                let value = self.power(b.re, e.re)?;
+                let mut eps = 0f64;
+                if b.eps != 0f64 {
+                    eps += e.re * self.power(b.re, e.re - 1f64)? * b.eps;
+                }
+                if e.eps != 0f64 {
+                    eps += value * b.re.ln() * e.eps;
+                }
+                Dual::new(value, eps)
+            }
+            Node::Factors(factors) => {
+                factors
+                    .iter()
+                    .try_fold(Dual::constant(1f64), |acc, (op, factor)| {
+                        let factor = self.dual(factor, var, map)?;
+                        match op {
+                            TokenType::Div if factor.re == 0f64 => Err(InterpreterError::Undefined),
+                            TokenType::Div => Ok(acc / factor),
+                            _ => Ok(acc * factor),
+                        }
+                    })?
+            }
+            Node::Terms(terms) => {
+                terms
+                    .iter()
+                    .try_fold(Dual::constant(0f64), |acc, (op, term)| {
+                        let term = self.dual(term, var, map)?;
+                        Ok(match op {
+                            TokenType::Minus => acc - term,
+                            _ => acc + term,
+                        })
+                    })?
+            }
+            Node::Derivative { .. } | Node::Command { .. } => {
+                self.dual(&self.visit(node, None)?, var, map)?
+            }
+            _ => {
+                return Err(InterpreterError::SolveError(String::from(
+                    "Not substituted",
+                )))
+            }
+        })
+    }
+
+    fn record(
+        &self,
+        node: &Node,
+        inputs: &HashMap<char, usize>,
+        map: &HashMap<char, f64>,
+        tape: &mut Tape,
+    ) -> InterpreterResult<usize> {
+        Ok(match node {
+            Node::Num(n) => tape.push(*n, vec![]),
+            Node::Var(v) => match inputs.get(v) {
+                Some(input) => *input,
+                None => tape.push(self.lookup(*v, map)?, vec![]),
+            },
+            Node::Func { func, arg } => {
+                let arg = self.record(arg, inputs, map, tape)?;
+                let x = tape.values[arg];
+                let value = self.apply(func, x)?;
+                tape.push(value, vec![(arg, slope(func, x, value))])
+            }
+            Node::Exponent { base, exponent } => {
+                let base = self.record(base, inputs, map, tape)?;
+                let exponent = self.record(exponent, inputs, map, tape)?;
+                let (b, e) = (tape.values[base], tape.values[exponent]);
+                let value = self.power(b, e)?;
+                let d_base = if e == 0f64 {
+                    0f64
+                } else {
+                    e * self.power(b, e - 1f64)?
+                };
+                let d_exponent = if b > 0f64 { value * b.ln() } else { 0f64 };
+                tape.push(value, vec![(base, d_base), (exponent, d_exponent)])
+            }
+            Node::Factors(factors) => {
+                let mut acc = tape.push(1f64, vec![]);
+                for (op, factor) in factors {
+                    let factor = self.record(factor, inputs, map, tape)?;
+                    let (a, b) = (tape.values[acc], tape.values[factor]);
+                    acc = match op {
+                        TokenType::Div if b == 0f64 => return Err(InterpreterError::Undefined),
+                        TokenType::Div => {
+                            tape.push(a / b, vec![(acc, 1f64 / b), (factor, -a / (b * b))])
+                        }
+                        _ => tape.push(a * b, vec![(acc, b), (factor, a)]),
+                    };
+                }
+                acc
+            }
+            Node::Terms(terms) => {
+                let mut parents = vec![];
+                let mut value = 0f64;
+                for (op, term) in terms {
+                    let term = self.record(term, inputs, map, tape)?;
+                    let sign = if *op == TokenType::Minus { -1f64 } else { 1f64 };
+                    value += sign * tape.values[term];
+                    parents.push((term, sign));
+                }
+                tape.push(value, parents)
+            }
+            Node::Derivative { .. } | Node::Command { .. } => {
+                self.record(&self.visit(node, None)?, inputs, map, tape)?
+            }
+            _ => {
+                return Err(InterpreterError::SolveError(String::from(
+                    "Not substituted",
+                )))
+            }
+        })
+    }
+
+    pub fn derivative_at(
+        &self,
+        f: &Node,
+        var: char,
+        map: &HashMap<char, f64>,
+    ) -> InterpreterResult<(f64, f64)> {
+        let Dual { re, eps } = self.dual(f, var, map)?;
+        Ok((re, eps))
+    }
+
+    pub fn gradient_at(
+        &self,
+        f: &Node,
+        vars: &[char],
+        map: &HashMap<char, f64>,
+    ) -> InterpreterResult<(f64, Vec<f64>)> {
+        let mut tape = Tape::default();
+        let inputs = InterpreterResult::<HashMap<char, usize>>::from_iter(
+            vars.iter()
+                .map(|var| Ok((*var, tape.push(self.lookup(*var, map)?, vec![])))),
+        )?;
+        let output = self.record(f, &inputs, map, &mut tape)?;
+        let adjoints = tape.adjoints(output);
+        Ok((
+            tape.values[output],
+            vars.iter().map(|var| adjoints[inputs[var]]).collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::interpreter::{parse, Interpreter};
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-12 * b.abs().max(1f64)
+    }
+
+    #[test]
+    fn forward_mode() {
+        let interpreter = Interpreter::new();
+        let map = HashMap::from([('x', 0.5)]);
+        for (f, value, slope) in [
+            ("x^3", 0.125, 0.75),
+            (
+                "sin(x)*e^x",
+                0.5f64.sin() * 0.5f64.exp(),
+                (0.5f64.sin() + 0.5f64.cos()) * 0.5f64.exp(),
+            ),
+            ("ln(x)/x", 0.5f64.ln() / 0.5, (1f64 - 0.5f64.ln()) / 0.25),
+            (
+                "x^x",
+                0.5f64.powf(0.5),
+                0.5f64.powf(0.5) * (0.5f64.ln() + 1f64),
+            ),
+        ] {
+            let (re, eps) = interpreter.derivative_at(&parse(f), 'x', &map).unwrap();
+            assert!(close(re, value) && close(eps, slope), "{f}: {re}, {eps}");
+        }
+    }
+
+    #[test]
+    fn reverse_mode() {
+        let interpreter = Interpreter::new();
+        let map = HashMap::from([('x', 1f64), ('y', 2f64)]);
+        let (value, gradient) = interpreter
+            .gradient_at(&parse("x^2*y+sin(x*y)"), &['x', 'y'], &map)
+            .unwrap();
+        assert!(close(value, 2f64 + 2f64.sin()));
+        assert!(close(gradient[0], 4f64 + 2f64 * 2f64.cos()));
+        assert!(close(gradient[1], 1f64 + 2f64.cos()));
+    }
+
+    #[test]
+    fn modes_agree() {
+        let interpreter = Interpreter::new();
+        let f = parse("sqrt(x^2+y^2)*cos(x-y)");
+        let map = HashMap::from([('x', 0.3), ('y', -1.2)]);
+        let (_, gradient) = interpreter.gradient_at(&f, &['x', 'y'], &map).unwrap();
+        for (var, partial) in ['x', 'y'].into_iter().zip(gradient) {
+            let (_, eps) = interpreter.derivative_at(&f, var, &map).unwrap();
+            assert!(close(eps, partial), "{var}");
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use crate::{
+    node::Node,
+    token::{CommandType, TokenType},
+};
+
+use super::{Interpreter, InterpreterError, InterpreterResult};
+
+impl Interpreter {
+    fn partial(&self, f: &Node, var: char) -> InterpreterResult<Node> {
+        Ok(self.simplify(&self.derive(f, &[(var, 1)], None)?))
+    }
+
+    fn gradient(&self, f: &Node, vars: &[char]) -> InterpreterResult<Vec<Node>> {
+        InterpreterResult::from_iter(vars.iter().map(|var| self.partial(f, *var)))
+    }
+
+    fn total(&self, terms: Vec<Node>) -> Node {
+        self.simplify(&Node::Terms(
+            terms
+                .into_iter()
+                .map(|term| (TokenType::Plus, term))
+                .collect(),
+        ))
+    }
+
+    pub fn vector_calculus(&self, command: CommandType, args: &[Node]) -> InterpreterResult<Node> {
+        let invalid = || InterpreterError::InvalidArguments(command);
+        let (f, rest) = args.split_first().ok_or_else(invalid)?;
+        let f = self.visit(f, None)?;
+        let mut vars = vec![];
+        let mut point = HashMap::new();
+        for arg in rest {
+            match arg {
+                Node::Equation { lhs, rhs } => {
+                    point.insert(
+                        lhs.as_var().ok_or_else(invalid)?,
+                        self.evaluate(rhs, &HashMap::new())?,
+                    );
+                }
+                arg => match self.visit(arg, None)? {
+                    Node::Vector(nodes) => vars.extend(
+                        nodes
+                            .iter()
+                            .map(Node::as_var)
+                            .collect::<Option<Vec<_>>>()
+                            .ok_or_else(invalid)?,
+                    ),
+                    node => vars.push(node.as_var().ok_or_else(invalid)?),
+                },
+            }
+        }
+        if vars.is_empty() {
+            vars = self.unknowns(&f);
+        }
+        let components = |f: &Node| match f {
+            Node::Vector(nodes) => Ok(nodes.clone()),
+            _ => Err(invalid()),
+        };
+        let result = match command {
+            CommandType::Grad => Node::Vector(self.gradient(&f, &vars)?),
+            CommandType::Jacobian => Node::Vector(InterpreterResult::from_iter(
+                components(&f)?
+                    .iter()
+                    .map(|f| Ok(Node::Vector(self.gradient(f, &vars)?))),
+            )?),
+            CommandType::Hessian => Node::Vector(InterpreterResult::from_iter(
+                self.gradient(&f, &vars)?
+                    .iter()
+                    .map(|f| Ok(Node::Vector(self.gradient(f, &vars)?))),
+            )?),
+            CommandType::Div => {
+                let f = components(&f)?;
+                if f.len() != vars.len() {
+                    return Err(invalid());
+                }
+                self.total(InterpreterResult::from_iter(
+                    f.iter().zip(&vars).map(|(f, var)| self.partial(f, *var)),
+                )?)
+            }
+            CommandType::Laplacian => self
+                .total(InterpreterResult::from_iter(vars.iter().map(|var| {
+                    Ok(self.simplify(&self.derive(&f, &[(*var, 2)], None)?))
+                }))?),
+            _ => return Err(invalid()),
+        };
+        if point.is_empty() {
+            Ok(result)
+        } else {
+            self.visit(&result, Some(&point))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::run;
+
+    fn show(line: &str) -> String {
+        run(line).unwrap().to_string()
+    }
+
+    #[test]
+    fn gradient() {
+        assert_eq!(show("grad(x^2*y, [x, y])"), "[2*x*y, x^2]");
+        assert_eq!(show("grad(x^2*y, x, y)"), "[2*x*y, x^2]");
+        assert_eq!(show("grad(x^2*y, [x, y], x=1, y=2)"), "[4, 1]");
+    }
+
+    #[test]
+    fn matrices() {
+        assert_eq!(show("jacobian([x*y, x+y], [x, y])"), "[[y, x], [1, 1]]");
+        assert_eq!(show("hessian(x^2*y, [x, y])"), "[[2*y, 2*x], [2*x, 0]]");
+    }
+
+    #[test]
+    fn divergence_and_laplacian() {
+        assert_eq!(show("div([x*y, y^2], [x, y])"), "3*y");
+        assert_eq!(show("laplacian(x^2+y^2, [x, y])"), "4");
+    }
+}
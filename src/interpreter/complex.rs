@@ -0,0 +1,171 @@
+use std::ops::{Add, Div, Mul, Sub};
+
+use crate::{
+    node::Node,
+    token::{FuncType, TokenType},
+};
+
+use super::Interpreter;
+
+pub const IMAGINARY: char = 'i';
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    pub fn abs(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    pub fn exp(self) -> Self {
+        let r = self.re.exp();
+        Self::new(r * self.im.cos(), r * self.im.sin())
+    }
+
+    pub fn ln(self) -> Self {
+        Self::new(self.abs().ln(), self.im.atan2(self.re))
+    }
+
+    pub fn pow(self, exponent: Self) -> Self {
+        if self == Self::new(0f64, 0f64) {
+            return self;
+        }
+        match exponent {
+            Self { re, im } if im == 0f64 && re.fract() == 0f64 && re.abs() <= 64f64 => {
+                let power = (0..re.abs() as u32).fold(Self::new(1f64, 0f64), |acc, _| acc * self);
+                if re < 0f64 {
+                    Self::new(1f64, 0f64) / power
+                } else {
+                    power
+                }
+            }
+            exponent => (exponent * self.ln()).exp(),
+        }
+    }
+
+    pub fn to_node(self) -> Node {
+        let clean = |n: f64| {
+            if (n - n.round()).abs() < 1e-9 {
+                n.round() + 0f64
+            } else {
+                n
+            }
+        };
+        let (re, im) = (clean(self.re), clean(self.im));
+        if im == 0f64 {
+            return Node::Num(re);
+        }
+        let imaginary = if im.abs() == 1f64 {
+            Node::Var(IMAGINARY)
+        } else {
+            Node::Factors(vec![
+                (TokenType::Mul, Node::Num(im.abs())),
+                (TokenType::Mul, Node::Var(IMAGINARY)),
+            ])
+        };
+        let sign = if im < 0f64 {
+            TokenType::Minus
+        } else {
+            TokenType::Plus
+        };
+        if re == 0f64 {
+            Node::Terms(vec![(sign, imaginary)])
+        } else {
+            Node::Terms(vec![(TokenType::Plus, Node::Num(re)), (sign, imaginary)])
+        }
+    }
+}
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+impl Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+impl Div for Complex {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        let denom = rhs.re * rhs.re + rhs.im * rhs.im;
+        Self::new(
+            (self.re * rhs.re + self.im * rhs.im) / denom,
+            (self.im * rhs.re - self.re * rhs.im) / denom,
+        )
+    }
+}
+
+impl Interpreter {
+    fn complex_value(&self, node: &Node) -> Option<Complex> {
+        Some(match node {
+            Node::Num(n) => Complex::new(*n, 0f64),
+            Node::Var(IMAGINARY) => Complex::new(0f64, 1f64),
+            Node::Terms(terms) => {
+                terms
+                    .iter()
+                    .try_fold(Complex::new(0f64, 0f64), |acc, (op, term)| {
+                        let term = self.complex_value(term)?;
+                        Some(match op {
+                            TokenType::Minus => acc - term,
+                            _ => acc + term,
+                        })
+                    })?
+            }
+            Node::Factors(factors) => {
+                factors
+                    .iter()
+                    .try_fold(Complex::new(1f64, 0f64), |acc, (op, factor)| {
+                        let factor = self.complex_value(factor)?;
+                        Some(match op {
+                            TokenType::Div => acc / factor,
+                            _ => acc * factor,
+                        })
+                    })?
+            }
+            Node::Exponent { base, exponent } => {
+                self.complex_value(base)?.pow(self.complex_value(exponent)?)
+            }
+            Node::Func { func, arg } => {
+                let arg = self.complex_value(arg)?;
+                match func {
+                    FuncType::Sqrt => arg.pow(Complex::new(0.5, 0f64)),
+                    FuncType::Ln => arg.ln(),
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        })
+    }
+
+    pub fn reduce_imaginary(&self, node: Node) -> Node {
+        if !self.complex || matches!(node, Node::Num(_)) || self.unknowns(&node) != [IMAGINARY] {
+            return node;
+        }
+        match self.complex_value(&node) {
+            Some(z) if z.re.is_finite() && z.im.is_finite() => z.to_node(),
+            _ => node,
+        }
+    }
+}
@@ -5,29 +5,204 @@ use crate::{
     token::{FuncType, TokenType},
 };
 
-use super::{Interpreter, InterpreterError, InterpreterResult};
+use super::{ode::unwrap, Interpreter, InterpreterError, InterpreterResult};
 
 #[derive(Debug, Clone)]
 pub enum DifferentiatorError {
     Equation,
+    Relation,
+    Mismatch {
+        derivative: Node,
+        point: Vec<(char, f64)>,
+        symbolic: f64,
+        numeric: f64,
+    },
+    Unverified {
+        derivative: Node,
+        checked: usize,
+    },
 }
 impl fmt::Display for DifferentiatorError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str(match self {
             Self::Equation => "cannot perform differentiation on an equation",
+            Self::Relation => "cannot perform differentiation on a relation",
+            Self::Mismatch {
+                derivative,
+                point,
+                symbolic,
+                numeric,
+            } => {
+                return write!(
+                    f,
+                    "check failed: {derivative} is {symbolic} but numerically {numeric} at ({})",
+                    point
+                        .iter()
+                        .map(|(var, val)| format!("{var} = {val}"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            }
+            Self::Unverified {
+                derivative,
+                checked,
+            } => {
+                return write!(
+                    f,
+                    "check failed: could not verify {derivative}, only {checked} sample points were defined"
+                )
+            }
         })
     }
 }
 
+fn implicit_order(node: &Node, var: char) -> Option<(&Node, u32)> {
+    match unwrap(node) {
+        eq @ Node::Equation { .. } => Some((eq, 0)),
+        Node::Derivative { derivative, vars } if vars.iter().all(|(inner, _)| *inner == var) => {
+            let (eq, order) = implicit_order(derivative, var)?;
+            Some((eq, order + vars.iter().map(|(_, order)| order).sum::<u32>()))
+        }
+        _ => None,
+    }
+}
+
 impl Interpreter {
+    pub fn derive(
+        &self,
+        node: &Node,
+        vars: &[(char, u32)],
+        ext: Option<&HashMap<char, f64>>,
+    ) -> InterpreterResult<Node> {
+        if let [(var, order)] = vars {
+            if let Some((eq, inner)) = implicit_order(node, *var) {
+                self.enter_steps();
+                let res = self.implicit(eq, *var, inner + order, ext);
+                self.leave_steps();
+                return res;
+            }
+        }
+        let mut res = node.clone();
+        for (var, order) in vars {
+            for _ in 0..*order {
+                self.enter_steps();
+                let next = self
+                    .differentiate(&res, *var, ext)
+                    .and_then(|derivative| Ok(self.simplify(&self.visit(&derivative, ext)?)));
+                self.leave_steps();
+                let next = next?;
+                if self.check {
+                    self.check_derivative(&res, *var, &next)?;
+                }
+                res = next;
+            }
+        }
+        Ok(res)
+    }
+
+    fn implicit(
+        &self,
+        eq: &Node,
+        var: char,
+        order: u32,
+        ext: Option<&HashMap<char, f64>>,
+    ) -> InterpreterResult<Node> {
+        let f = self.visit(&self.move_equation(eq)?, ext)?;
+        let others = self
+            .unknowns(&f)
+            .into_iter()
+            .filter(|v| *v != var)
+            .collect::<Vec<_>>();
+        let dependent = match others[..] {
+            [dependent] => dependent,
+            _ if others.contains(&'y') => 'y',
+            _ => {
+                return Err(InterpreterError::DifferentiatorError(
+                    DifferentiatorError::Equation,
+                ))
+            }
+        };
+        let slope = self.visit(
+            &Node::Factors(vec![
+                (TokenType::Mul, Node::Num(-1f64)),
+                (TokenType::Mul, self.differentiate(&f, var, ext)?),
+                (TokenType::Div, self.differentiate(&f, dependent, ext)?),
+            ]),
+            ext,
+        )?;
+        self.note_step(|| {
+            format!(
+                "d{dependent}/d{var} = -(∂F/∂{var})/(∂F/∂{dependent}) = {}  (implicit differentiation)",
+                self.simplify(&slope)
+            )
+        });
+        let mut derivative = slope.clone();
+        for _ in 1..order {
+            derivative = self.visit(
+                &Node::Terms(vec![
+                    (TokenType::Plus, self.differentiate(&derivative, var, ext)?),
+                    (
+                        TokenType::Plus,
+                        Node::Factors(vec![
+                            (
+                                TokenType::Mul,
+                                self.differentiate(&derivative, dependent, ext)?,
+                            ),
+                            (TokenType::Mul, slope.clone()),
+                        ]),
+                    ),
+                ]),
+                ext,
+            )?;
+        }
+        Ok(derivative)
+    }
+
     pub fn differentiate(
         &self,
         node: &Node,
         var: char,
         ext: Option<&HashMap<char, f64>>,
+    ) -> InterpreterResult<Node> {
+        let transparent = match node {
+            Node::Num(_) | Node::Var(_) | Node::Estimate { .. } => true,
+            Node::Terms(terms) => matches!(terms[..], [(TokenType::Plus, _)]),
+            Node::Factors(factors) => matches!(factors[..], [(TokenType::Mul, _)]),
+            _ => false,
+        };
+        if transparent || !self.tracing() {
+            return self.differentiate_node(node, var, ext);
+        }
+        let step = self.open_step();
+        let res = self.differentiate_node(node, var, ext);
+        self.close_step(
+            step,
+            res.as_ref().ok().map(|derivative| {
+                let derivative = self
+                    .visit(derivative, ext)
+                    .map(|visited| self.simplify(&visited))
+                    .unwrap_or_else(|_| derivative.clone());
+                format!(
+                    "{} = {derivative}  ({})",
+                    Node::Derivative {
+                        derivative: Box::new(self.simplify(node)),
+                        vars: vec![(var, 1)],
+                    },
+                    self.differentiation_rule(node, var)
+                )
+            }),
+        );
+        res
+    }
+
+    fn differentiate_node(
+        &self,
+        node: &Node,
+        var: char,
+        ext: Option<&HashMap<char, f64>>,
     ) -> InterpreterResult<Node> {
         Ok(match node {
-            Node::Num(_) => Node::Num(0f64),
+            Node::Num(_) | Node::Estimate { .. } => Node::Num(0f64),
             Node::Var(ch) => {
                 if ch == &var {
                     Node::Num(1f64)
@@ -124,6 +299,19 @@ impl Interpreter {
                                         ),
                                     ]),
                                 )]),
+                                FuncType::Sqrt => Node::Factors(vec![(
+                                    TokenType::Div,
+                                    Node::Factors(vec![
+                                        (TokenType::Mul, Node::Num(2f64)),
+                                        (
+                                            TokenType::Mul,
+                                            Node::Func {
+                                                func: FuncType::Sqrt,
+                                                arg: Box::new(visited_arg),
+                                            },
+                                        ),
+                                    ]),
+                                )]),
                             },
                         ),
                     ]),
@@ -222,6 +410,7 @@ impl Interpreter {
                         ))
                     }),
                 )?);
+                let mut quotient = mul.into_iter().cloned().collect::<Vec<_>>();
                 for (_, divisor) in div {
                     res = Node::Factors(vec![
                         (
@@ -230,7 +419,7 @@ impl Interpreter {
                                 (
                                     TokenType::Plus,
                                     Node::Factors(vec![
-                                        (TokenType::Mul, self.differentiate(&res, var, ext)?),
+                                        (TokenType::Mul, res),
                                         (TokenType::Mul, divisor.clone()),
                                     ]),
                                 ),
@@ -238,7 +427,7 @@ impl Interpreter {
                                     TokenType::Minus,
                                     Node::Factors(vec![
                                         (TokenType::Mul, self.differentiate(divisor, var, ext)?),
-                                        (TokenType::Mul, res.clone()),
+                                        (TokenType::Mul, Node::Factors(quotient.clone())),
                                     ]),
                                 ),
                             ]),
@@ -251,21 +440,40 @@ impl Interpreter {
                             },
                         ),
                     ]);
+                    quotient.push((TokenType::Div, divisor.clone()));
                 }
                 res
             }
             Node::Terms(terms) => Node::Terms(InterpreterResult::from_iter(terms.iter().map(
                 |(t, term)| Ok((*t, self.differentiate(&self.visit(term, ext)?, var, ext)?)),
             ))?),
-            Node::Derivative {
-                derivative,
-                var: var2,
-            } => self.differentiate(
-                &self.visit(&self.differentiate(derivative, *var2, ext)?, ext)?,
-                var,
-                ext,
-            )?,
-            Node::Equation { .. } => {
+            Node::Derivative { .. } if implicit_order(node, var).is_some() => {
+                let (eq, order) = implicit_order(node, var).expect("checked above");
+                self.implicit(eq, var, order + 1, ext)?
+            }
+            Node::Derivative { derivative, vars } => {
+                self.differentiate(&self.derive(derivative, vars, None)?, var, ext)?
+            }
+            Node::Vector(nodes) => Node::Vector(InterpreterResult::from_iter(
+                nodes.iter().map(|node| self.differentiate(node, var, ext)),
+            )?),
+            Node::Bool(_)
+            | Node::Str(_)
+            | Node::Relation { .. }
+            | Node::Set { .. }
+            | Node::Range { .. }
+            | Node::Approach { .. }
+            | Node::Table { .. }
+            | Node::Fit { .. } => {
+                return Err(InterpreterError::DifferentiatorError(
+                    DifferentiatorError::Relation,
+                ))
+            }
+            Node::Command { .. } | Node::Prime { .. } => {
+                self.differentiate(&self.visit(node, ext)?, var, ext)?
+            }
+            Node::Equation { .. } => self.implicit(node, var, 1, ext)?,
+            Node::System(_) => {
                 return Err(InterpreterError::DifferentiatorError(
                     DifferentiatorError::Equation,
                 ))
@@ -273,3 +481,36 @@ impl Interpreter {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{interpreter::run, node::Node};
+
+    #[test]
+    fn implicit_derivatives_of_a_circle() {
+        assert_eq!(run("d/dx(x^2+y^2=25)").unwrap().to_string(), "-x/y");
+        assert_eq!(run("d/dx(x^2+y^2=25)|x=3|y=4").unwrap(), Node::Num(-0.75));
+        assert_eq!(
+            run("d^2/dx^2(x^2+y^2=25)|x=3|y=4").unwrap(),
+            Node::Num(-25f64 / 64f64)
+        );
+        assert_eq!(
+            run("d/dx(d/dx(x^2+y^2=25))|x=3|y=4").unwrap(),
+            Node::Num(-25f64 / 64f64)
+        );
+    }
+
+    #[test]
+    fn implicit_derivative_of_a_product() {
+        let Node::Num(slope) = run("d/dx(x*y+y^3=2)|x=1|y=1").unwrap() else {
+            panic!()
+        };
+        assert!((slope + 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn derivatives_inside_equations_are_taken_before_solving() {
+        assert_eq!(run("x + d/dx(3x) = 5").unwrap().to_string(), "x = 2");
+        assert_eq!(run("d/dx(x^2) = d/dx(3x)").unwrap().to_string(), "x = 3/2");
+    }
+}
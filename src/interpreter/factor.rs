@@ -0,0 +1,282 @@
+use crate::{node::Node, token::TokenType};
+
+use super::{
+    polynomial::Polynomial, rational::Rational, Interpreter, InterpreterError, InterpreterResult,
+};
+
+type Poly = Vec<i128>;
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn trim(mut p: Poly) -> Poly {
+    while p.last() == Some(&0) {
+        p.pop();
+    }
+    p
+}
+
+fn degree(p: &Poly) -> usize {
+    p.len().saturating_sub(1)
+}
+
+fn primitive(p: Poly) -> Poly {
+    let content = p.iter().fold(0, |acc, c| gcd(acc, *c));
+    if content == 0 {
+        return p;
+    }
+    let content = content * p.last().map_or(1, |c| c.signum());
+    p.into_iter().map(|c| c / content).collect()
+}
+
+fn derivative(p: &Poly) -> Option<Poly> {
+    p.iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, c)| c.checked_mul(i as i128))
+        .collect::<Option<Poly>>()
+        .map(trim)
+}
+
+fn subtract(a: &Poly, b: &Poly) -> Option<Poly> {
+    (0..a.len().max(b.len()))
+        .map(|i| {
+            a.get(i)
+                .copied()
+                .unwrap_or(0)
+                .checked_sub(b.get(i).copied().unwrap_or(0))
+        })
+        .collect::<Option<Poly>>()
+        .map(trim)
+}
+
+fn pseudo_remainder(a: &Poly, b: &Poly) -> Option<Poly> {
+    let lead = *b.last()?;
+    let mut r = a.clone();
+    while !r.is_empty() && r.len() >= b.len() {
+        let top = *r.last()?;
+        let shift = r.len() - b.len();
+        r = r
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let sub = match i.checked_sub(shift) {
+                    Some(j) => top.checked_mul(b[j])?,
+                    None => 0,
+                };
+                c.checked_mul(lead)?.checked_sub(sub)
+            })
+            .collect::<Option<Poly>>()?;
+        r = primitive(trim(r));
+    }
+    Some(r)
+}
+
+fn poly_gcd(a: &Poly, b: &Poly) -> Option<Poly> {
+    let (mut a, mut b) = (primitive(a.clone()), primitive(b.clone()));
+    while !b.is_empty() {
+        let r = pseudo_remainder(&a, &b)?;
+        (a, b) = (b, primitive(r));
+    }
+    Some(a)
+}
+
+fn divide(a: &Poly, b: &Poly) -> Option<Poly> {
+    let lead = *b.last()?;
+    let mut r = a.clone();
+    let mut quotient = vec![0; (a.len() + 1).saturating_sub(b.len())];
+    while !r.is_empty() && r.len() >= b.len() {
+        let top = *r.last()?;
+        if top % lead != 0 {
+            return None;
+        }
+        let c = top / lead;
+        let shift = r.len() - b.len();
+        quotient[shift] = c;
+        for (j, bj) in b.iter().enumerate() {
+            r[shift + j] = r[shift + j].checked_sub(c.checked_mul(*bj)?)?;
+        }
+        r = trim(r);
+    }
+    r.is_empty().then(|| trim(quotient))
+}
+
+fn square_free(p: &Poly) -> Option<Vec<(Poly, u32)>> {
+    let dp = derivative(p)?;
+    let a = poly_gcd(p, &dp)?;
+    let mut b = divide(p, &a)?;
+    let mut d = subtract(&divide(&dp, &a)?, &derivative(&b)?)?;
+    let mut factors = vec![];
+    let mut multiplicity = 1;
+    while degree(&b) > 0 {
+        let a = poly_gcd(&b, &d)?;
+        b = divide(&b, &a)?;
+        d = subtract(&divide(&d, &a)?, &derivative(&b)?)?;
+        if degree(&a) > 0 {
+            factors.push((a, multiplicity));
+        }
+        multiplicity += 1;
+    }
+    Some(factors)
+}
+
+fn divisors(n: i128) -> Option<Vec<i128>> {
+    let n = n.abs();
+    if n == 0 || n > 1_000_000_000_000 {
+        return None;
+    }
+    let mut divisors = vec![];
+    let mut i = 1;
+    while i * i <= n {
+        if n % i == 0 {
+            divisors.push(i);
+            if i * i != n {
+                divisors.push(n / i);
+            }
+        }
+        i += 1;
+    }
+    Some(divisors)
+}
+
+fn is_root(p: &Poly, num: i128, den: i128) -> bool {
+    let n = degree(p) as u32;
+    p.iter().enumerate().try_fold(0i128, |acc, (i, c)| {
+        let term = c
+            .checked_mul(num.checked_pow(i as u32)?)?
+            .checked_mul(den.checked_pow(n - i as u32)?)?;
+        acc.checked_add(term)
+    }) == Some(0)
+}
+
+fn linear_factors(p: Poly) -> Vec<Poly> {
+    let (Some(lead), Some(constant)) = (p.last().and_then(|c| divisors(*c)), divisors(p[0])) else {
+        return vec![p];
+    };
+    let mut rest = p;
+    let mut factors = vec![];
+    for den in &lead {
+        for num in constant.iter().flat_map(|num| [*num, -num]) {
+            if gcd(num, *den) != 1 || degree(&rest) < 1 || !is_root(&rest, num, *den) {
+                continue;
+            }
+            let linear = vec![-num, *den];
+            match divide(&rest, &linear) {
+                Some(quotient) => {
+                    factors.push(linear);
+                    rest = quotient;
+                }
+                None => continue,
+            }
+        }
+    }
+    if degree(&rest) > 0 {
+        factors.push(rest);
+    }
+    factors.sort_by_key(|f| (f.len(), f.clone()));
+    factors
+}
+
+fn to_node(p: &Poly, var: char) -> Node {
+    Polynomial::new(p.iter().map(|c| *c as f64).collect()).to_node(var)
+}
+
+impl Interpreter {
+    pub fn factor(&self, node: &Node, var: char) -> InterpreterResult<Node> {
+        let error =
+            || InterpreterError::SolveError(format!("Cannot factor {node} over the rationals"));
+        let poly = self.polynomial(node, var).ok_or_else(error)?;
+        let coefficients = poly
+            .coefficients()
+            .iter()
+            .map(|c| Rational::from_f64(*c))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(error)?;
+        if coefficients.is_empty() {
+            return Ok(Node::Num(0f64));
+        }
+        let lcm = coefficients.iter().try_fold(1i128, |acc, c| {
+            let den = c.denominator() as i128;
+            (acc / gcd(acc, den)).checked_mul(den)
+        });
+        let lcm = lcm.ok_or_else(error)?;
+        let scaled = coefficients
+            .iter()
+            .map(|c| (c.numerator() as i128).checked_mul(lcm / c.denominator() as i128))
+            .collect::<Option<Poly>>()
+            .ok_or_else(error)?;
+        let shift = scaled.iter().take_while(|c| **c == 0).count();
+        let scaled = scaled[shift..].to_vec();
+        let primitive_part = primitive(scaled.clone());
+        let constant = Rational::new(
+            i64::try_from(scaled[scaled.len() - 1] / primitive_part[primitive_part.len() - 1])
+                .map_err(|_| error())?,
+            i64::try_from(lcm).map_err(|_| error())?,
+        );
+        let mut factors = vec![];
+        if shift > 0 {
+            factors.push((vec![0, 1], shift as u32));
+        }
+        for (factor, multiplicity) in square_free(&primitive_part).ok_or_else(error)? {
+            factors.extend(
+                linear_factors(factor)
+                    .into_iter()
+                    .map(|factor| (factor, multiplicity)),
+            );
+        }
+        let mut product = match constant.to_node() {
+            Node::Num(n) if n == 1f64 && !factors.is_empty() => vec![],
+            Node::Num(n) if n == -1f64 && !factors.is_empty() => {
+                vec![(TokenType::Mul, Node::Num(-1f64))]
+            }
+            Node::Factors(factors) => factors,
+            constant => vec![(TokenType::Mul, constant)],
+        };
+        product.extend(factors.iter().map(|(factor, multiplicity)| {
+            let factor = to_node(factor, var);
+            (
+                TokenType::Mul,
+                match multiplicity {
+                    1 => factor,
+                    m => Node::Exponent {
+                        base: Box::new(factor),
+                        exponent: Box::new(Node::Num(*m as f64)),
+                    },
+                },
+            )
+        }));
+        Ok(match &product[..] {
+            [(TokenType::Mul, Node::Num(n)), rest @ ..] if *n == -1f64 => {
+                Node::Terms(vec![(TokenType::Minus, Node::Factors(rest.to_vec()))])
+            }
+            [(TokenType::Mul, factor)] => factor.clone(),
+            _ => Node::Factors(product),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::run;
+
+    fn show(line: &str) -> String {
+        run(line).unwrap().to_string()
+    }
+
+    #[test]
+    fn expand_products_and_powers() {
+        assert_eq!(show("expand((x+1)^3)"), "x^3+3*x^2+3*x+1");
+        assert_eq!(show("expand((x-2)*(x+3))"), "x^2+x-6");
+    }
+
+    #[test]
+    fn factor_polynomials() {
+        assert_eq!(show("factor(x^3-6x^2+11x-6)"), "(x-3)*(x-2)*(x-1)");
+        assert_eq!(show("factor(2x^2-8)"), "2*(x-2)*(x+2)");
+    }
+}
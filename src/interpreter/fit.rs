@@ -0,0 +1,246 @@
+use std::{collections::HashMap, fs};
+
+use crate::{node::Node, token::CommandType};
+
+use super::{
+    linalg::{self, Lu},
+    Interpreter, InterpreterError, InterpreterResult,
+};
+
+fn read_csv(path: &str) -> InterpreterResult<Vec<(f64, f64)>> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| InterpreterError::SolveError(format!("Cannot read {path}: {err}")))?;
+    Ok(text
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(',').map(|field| field.trim().parse::<f64>());
+            Some((fields.next()?.ok()?, fields.next()?.ok()?))
+        })
+        .collect())
+}
+
+fn gram(jacobian: &[Vec<f64>], m: usize) -> Vec<Vec<f64>> {
+    (0..m)
+        .map(|i| {
+            (0..m)
+                .map(|j| jacobian.iter().map(|row| row[i] * row[j]).sum())
+                .collect()
+        })
+        .collect()
+}
+
+impl Interpreter {
+    fn residuals(
+        &self,
+        model: &Node,
+        var: char,
+        params: &[char],
+        values: &[f64],
+        points: &[(f64, f64)],
+    ) -> InterpreterResult<Vec<f64>> {
+        let mut at = params
+            .iter()
+            .copied()
+            .zip(values.iter().copied())
+            .collect::<HashMap<_, _>>();
+        InterpreterResult::from_iter(points.iter().map(|(x, y)| {
+            at.insert(var, *x);
+            Ok(y - self.evaluate(model, &at)?)
+        }))
+    }
+
+    fn fit_jacobian(
+        &self,
+        model: &Node,
+        var: char,
+        params: &[char],
+        values: &[f64],
+        points: &[(f64, f64)],
+    ) -> InterpreterResult<Vec<Vec<f64>>> {
+        let mut at = params
+            .iter()
+            .copied()
+            .zip(values.iter().copied())
+            .collect::<HashMap<_, _>>();
+        InterpreterResult::from_iter(points.iter().map(|(x, _)| {
+            at.insert(var, *x);
+            Ok(self.gradient_at(model, params, &at)?.1)
+        }))
+    }
+
+    pub fn fit(&self, args: &[Node]) -> InterpreterResult<Node> {
+        let invalid = || InterpreterError::InvalidArguments(CommandType::Fit);
+        let error = |s: &str| InterpreterError::SolveError(String::from(s));
+        let (model, rest) = args.split_first().ok_or_else(invalid)?;
+        let origin = HashMap::new();
+        let mut points = vec![];
+        let mut guesses = vec![];
+        for arg in rest {
+            let arg = match arg {
+                Node::Equation { .. } | Node::Str(_) => arg.clone(),
+                arg => self.visit(arg, None)?,
+            };
+            match &arg {
+                Node::Vector(pair) => match &pair[..] {
+                    [x, y] => points.push((self.evaluate(x, &origin)?, self.evaluate(y, &origin)?)),
+                    _ => return Err(invalid()),
+                },
+                Node::Str(path) => points.extend(read_csv(path)?),
+                Node::Table { rows, .. } => {
+                    points.extend(rows.iter().filter_map(|row| match row[..] {
+                        [x, y, ..] => Some((x, y)),
+                        _ => None,
+                    }))
+                }
+                Node::Equation { lhs, rhs } => guesses.push((
+                    lhs.as_var().ok_or_else(invalid)?,
+                    self.evaluate(rhs, &origin)?,
+                )),
+                node => guesses.push((node.as_var().ok_or_else(invalid)?, 1f64)),
+            }
+        }
+        let unknowns = self.unknowns(model);
+        if guesses.is_empty() {
+            if !unknowns.contains(&'x') {
+                return Err(error("Specify the parameters to fit"));
+            }
+            guesses = unknowns
+                .iter()
+                .filter(|var| **var != 'x')
+                .map(|var| (*var, 1f64))
+                .collect();
+        }
+        let (params, mut values): (Vec<char>, Vec<f64>) = guesses.into_iter().unzip();
+        let [var] = unknowns
+            .iter()
+            .copied()
+            .filter(|var| !params.contains(var))
+            .collect::<Vec<_>>()[..]
+        else {
+            return Err(error("Model must have exactly one independent variable"));
+        };
+        let (n, m) = (points.len(), params.len());
+        if n < m {
+            return Err(error("Need at least as many data points as parameters"));
+        }
+        let mut residuals = self.residuals(model, var, &params, &values, &points)?;
+        let mut sse = linalg::dot(&residuals, &residuals);
+        let mut lambda = 1e-3;
+        for _ in 0..500 {
+            let jacobian = self.fit_jacobian(model, var, &params, &values, &points)?;
+            let jtj = gram(&jacobian, m);
+            let jtr = (0..m)
+                .map(|i| {
+                    jacobian
+                        .iter()
+                        .zip(&residuals)
+                        .map(|(row, r)| row[i] * r)
+                        .sum()
+                })
+                .collect::<Vec<f64>>();
+            let mut improved = false;
+            while lambda < 1e12 {
+                let mut damped = jtj.clone();
+                for (i, row) in damped.iter_mut().enumerate() {
+                    row[i] += lambda * jtj[i][i].max(1e-12);
+                }
+                if let Some(lu) = Lu::decompose(&damped) {
+                    let step = lu.solve(&jtr);
+                    let next = values
+                        .iter()
+                        .zip(&step)
+                        .map(|(v, s)| v + s)
+                        .collect::<Vec<_>>();
+                    if let Ok(next_residuals) = self.residuals(model, var, &params, &next, &points)
+                    {
+                        let next_sse = linalg::dot(&next_residuals, &next_residuals);
+                        if next_sse <= sse {
+                            let converged = sse - next_sse <= 1e-15 * sse.max(1e-300)
+                                || linalg::norm(&step) <= 1e-12 * linalg::norm(&values).max(1f64);
+                            (values, residuals, sse) = (next, next_residuals, next_sse);
+                            lambda = (lambda / 10f64).max(1e-12);
+                            improved = !converged;
+                            break;
+                        }
+                    }
+                }
+                lambda *= 10f64;
+            }
+            if !improved {
+                break;
+            }
+        }
+        let jacobian = self.fit_jacobian(model, var, &params, &values, &points)?;
+        let jtj = gram(&jacobian, m);
+        let variance = if n > m {
+            sse / (n - m) as f64
+        } else {
+            f64::NAN
+        };
+        let errors = match Lu::decompose(&jtj) {
+            Some(lu) => (0..m)
+                .map(|i| {
+                    let unit = (0..m)
+                        .map(|j| if i == j { 1f64 } else { 0f64 })
+                        .collect::<Vec<_>>();
+                    (variance * lu.solve(&unit)[i]).sqrt()
+                })
+                .collect(),
+            None => vec![f64::NAN; m],
+        };
+        Ok(Node::Fit {
+            params: params
+                .into_iter()
+                .zip(values)
+                .zip(errors)
+                .map(|((param, value), error)| (param, value, error))
+                .collect(),
+            residuals,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interpreter::{run, InterpreterError},
+        node::Node,
+    };
+
+    fn params(line: &str) -> Vec<(char, f64)> {
+        let Ok(Node::Fit { params, .. }) = run(line) else {
+            panic!("{line}")
+        };
+        params
+            .into_iter()
+            .map(|(param, value, _)| (param, value))
+            .collect()
+    }
+
+    #[test]
+    fn exponential_model() {
+        let found = params("fit(a*e^(b*x), (0,2), (1,2e), (2,2e^2), (3,2e^3), a, b)");
+        for ((param, value), expected) in found.into_iter().zip([('a', 2f64), ('b', 1f64)]) {
+            assert_eq!(param, expected.0);
+            assert!((value - expected.1).abs() < 1e-8, "{param} = {value}");
+        }
+    }
+
+    #[test]
+    fn parameters_default_to_the_other_unknowns() {
+        let found = params("fit(m*x+c, (0,1), (1,3), (2,5))");
+        let expected = [('c', 1f64), ('m', 2f64)];
+        for ((param, value), expected) in found.into_iter().zip(expected) {
+            assert_eq!(param, expected.0);
+            assert!((value - expected.1).abs() < 1e-8, "{param} = {value}");
+        }
+    }
+
+    #[test]
+    fn too_few_points() {
+        assert!(matches!(
+            run("fit(a*e^(b*x), (0,2), a, b)"),
+            Err(InterpreterError::SolveError(_))
+        ));
+    }
+}
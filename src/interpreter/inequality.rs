@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+
+use crate::{
+    node::{Interval, Node},
+    token::TokenType,
+};
+
+use super::{Interpreter, InterpreterError, InterpreterResult};
+
+const SAMPLES: usize = 1000;
+const QUIET_SEGMENTS: usize = 4;
+const MAX_RADIUS: f64 = 1e15;
+const MAX_BOUNDARIES: usize = 100;
+
+fn holds(op: TokenType, value: f64) -> bool {
+    match op {
+        TokenType::Less => value < 0f64,
+        TokenType::LessEqual => value <= 0f64,
+        TokenType::Greater => value > 0f64,
+        TokenType::GreaterEqual => value >= 0f64,
+        TokenType::NotEqual => value != 0f64,
+        _ => unreachable!(),
+    }
+}
+
+fn snap(value: f64) -> f64 {
+    if value.abs() < 1e-9 {
+        0f64
+    } else {
+        value
+    }
+}
+
+fn snap_int(x: f64) -> f64 {
+    if (x - x.round()).abs() < 1e-9 {
+        x.round() + 0f64
+    } else {
+        x
+    }
+}
+
+fn settled(a: f64, b: f64, c: f64) -> bool {
+    let (d1, d2) = (b - a, c - b);
+    if d2 == 0f64 || d2.signum() == c.signum() {
+        return true;
+    }
+    let ratio = d2 / d1;
+    if !(0f64..1f64).contains(&ratio) {
+        return false;
+    }
+    let limit = c + d2 * ratio / (1f64 - ratio);
+    limit * c.signum() >= -1e-9 * d2.abs()
+}
+
+impl Interpreter {
+    fn sample(&self, f: &Node, var: char, x: f64) -> Option<f64> {
+        self.evaluate(f, &HashMap::from([(var, x)]))
+            .ok()
+            .filter(|v| v.is_finite())
+    }
+
+    fn refine(&self, f: &Node, var: char, mut a: f64, mut b: f64) -> f64 {
+        let eq = Node::Equation {
+            lhs: Box::new(f.clone()),
+            rhs: Box::new(Node::Num(0f64)),
+        };
+        if let Ok(root) = self.solve_equation(&eq, var, (a + b) / 2f64) {
+            if a <= root && root <= b {
+                return snap_int(root);
+            }
+        }
+        let sign = |x| self.sample(f, var, x).map(f64::signum);
+        let left = sign(a);
+        for _ in 0..100 {
+            let mid = (a + b) / 2f64;
+            if sign(mid) == left {
+                a = mid;
+            } else {
+                b = mid;
+            }
+        }
+        snap_int((a + b) / 2f64)
+    }
+
+    fn scan(&self, f: &Node, var: char, start: f64, end: f64, points: &mut Vec<f64>) -> usize {
+        let found = points.len();
+        let mut prev = (start, self.sample(f, var, start));
+        for i in 1..=SAMPLES {
+            let x = start + (end - start) * i as f64 / SAMPLES as f64;
+            let curr = (x, self.sample(f, var, x));
+            let (a, b) = (prev.0.min(x), prev.0.max(x));
+            match (prev.1, curr.1) {
+                (Some(0f64), _) => points.push(prev.0),
+                (Some(p), Some(c)) if c != 0f64 && p.signum() != c.signum() => {
+                    points.push(self.refine(f, var, a, b))
+                }
+                (Some(_), None) | (None, Some(_)) => points.push(self.refine(f, var, a, b)),
+                _ => {}
+            }
+            prev = curr;
+        }
+        if prev.1 == Some(0f64) {
+            points.push(prev.0);
+        }
+        points.len() - found
+    }
+
+    fn scan_to_infinity(
+        &self,
+        f: &Node,
+        var: char,
+        direction: f64,
+        points: &mut Vec<f64>,
+    ) -> InterpreterResult<()> {
+        let incomplete = || {
+            InterpreterError::SolveError(String::from(
+                "Could not find every boundary of the inequality",
+            ))
+        };
+        let mut radius = 1f64;
+        let mut quiet = 0;
+        loop {
+            if quiet >= QUIET_SEGMENTS {
+                let ends = [radius / 4f64, radius / 2f64, radius]
+                    .map(|r| self.sample(f, var, direction * r));
+                match ends {
+                    [None, None, None] => return Ok(()),
+                    [Some(a), Some(b), Some(c)] if settled(a, b, c) => return Ok(()),
+                    _ => {}
+                }
+            }
+            if radius > MAX_RADIUS || points.len() > MAX_BOUNDARIES {
+                return Err(incomplete());
+            }
+            let found = self.scan(
+                f,
+                var,
+                direction * radius,
+                direction * 2f64 * radius,
+                points,
+            );
+            quiet = if found == 0 { quiet + 1 } else { 0 };
+            radius *= 2f64;
+        }
+    }
+
+    fn boundaries(&self, f: &Node, var: char) -> InterpreterResult<Vec<f64>> {
+        let eq = Node::Equation {
+            lhs: Box::new(f.clone()),
+            rhs: Box::new(Node::Num(0f64)),
+        };
+        let mut points = if self.polynomial(f, var).is_some() {
+            match self.solve_for(&eq, var) {
+                Ok(roots) => InterpreterResult::from_iter(
+                    roots
+                        .iter()
+                        .map(|root| self.evaluate(root, &HashMap::new())),
+                )?,
+                Err(_) => vec![],
+            }
+        } else {
+            let mut points = vec![];
+            self.scan(f, var, -1f64, 1f64, &mut points);
+            for direction in [1f64, -1f64] {
+                self.scan_to_infinity(f, var, direction, &mut points)?;
+            }
+            points
+        };
+        points.sort_by(f64::total_cmp);
+        points.dedup_by(|a, b| (*a - *b).abs() < 1e-9);
+        Ok(points)
+    }
+
+    pub fn solve_inequality(
+        &self,
+        f: &Node,
+        op: TokenType,
+        var: char,
+    ) -> InterpreterResult<Vec<Interval>> {
+        let points = self.boundaries(f, var)?;
+        let test = |x| self.sample(f, var, x).is_some_and(|v| holds(op, v));
+        let test_boundary = |x| self.sample(f, var, x).is_some_and(|v| holds(op, snap(v)));
+        let mut pieces = vec![];
+        let mut start = f64::NEG_INFINITY;
+        for point in points.iter().copied().chain([f64::INFINITY]) {
+            let probe = match (start.is_finite(), point.is_finite()) {
+                (true, true) => (start + point) / 2f64,
+                (true, false) => start + 1f64,
+                (false, true) => point - 1f64,
+                (false, false) => 0f64,
+            };
+            if test(probe) {
+                pieces.push(Interval {
+                    start,
+                    end: point,
+                    start_closed: false,
+                    end_closed: false,
+                });
+            }
+            if point.is_finite() && test_boundary(point) {
+                pieces.push(Interval {
+                    start: point,
+                    end: point,
+                    start_closed: true,
+                    end_closed: true,
+                });
+            }
+            start = point;
+        }
+        let mut intervals: Vec<Interval> = vec![];
+        for piece in pieces {
+            match intervals.last_mut() {
+                Some(last)
+                    if last.end == piece.start && (last.end_closed || piece.start_closed) =>
+                {
+                    last.end = piece.end;
+                    last.end_closed = piece.end_closed;
+                }
+                _ => intervals.push(piece),
+            }
+        }
+        Ok(intervals)
+    }
+
+    pub fn visit_relation(
+        &self,
+        lhs: &Node,
+        op: TokenType,
+        rhs: &Node,
+        ext: Option<&HashMap<char, f64>>,
+    ) -> InterpreterResult<Node> {
+        let (lhs, rhs) = (self.visit(lhs, ext)?, self.visit(rhs, ext)?);
+        if let (Node::Num(l), Node::Num(r)) = (&lhs, &rhs) {
+            return Ok(Node::Bool(holds(op, l - r)));
+        }
+        let f = Node::Terms(vec![(TokenType::Plus, lhs), (TokenType::Minus, rhs)]);
+        let [var] = self.unknowns(&f)[..] else {
+            return Err(InterpreterError::SolveError(String::from(
+                "Cannot determine the unknown",
+            )));
+        };
+        Ok(Node::Set {
+            var,
+            intervals: self.solve_inequality(&f, op, var)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interpreter::{run, InterpreterError},
+        node::{Interval, Node},
+    };
+
+    fn intervals(line: &str) -> Vec<Interval> {
+        match run(line) {
+            Ok(Node::Set { intervals, .. }) => intervals,
+            other => panic!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn boundary_beyond_initial_range() {
+        let [interval] = &intervals("ln(x) > 5")[..] else {
+            panic!()
+        };
+        assert!((interval.start - 5f64.exp()).abs() < 1e-9);
+        assert_eq!(interval.end, f64::INFINITY);
+    }
+
+    #[test]
+    fn small_threshold() {
+        let [interval] = &intervals("e^(-x) < 0.0000000001")[..] else {
+            panic!()
+        };
+        assert!((interval.start - 1e10f64.ln()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn undefined_side_is_excluded() {
+        let [interval] = &intervals("1/x > 2")[..] else {
+            panic!()
+        };
+        assert_eq!((interval.start, interval.end), (0f64, 0.5));
+    }
+
+    #[test]
+    fn boundary_of_an_oscillating_function() {
+        let [interval] = &intervals("x + sin(x) > 50")[..] else {
+            panic!()
+        };
+        assert!((interval.start + interval.start.sin() - 50f64).abs() < 1e-9);
+        assert_eq!(interval.end, f64::INFINITY);
+    }
+
+    #[test]
+    fn unbounded_boundaries_are_an_error() {
+        assert!(matches!(
+            run("sin(x) > 0"),
+            Err(InterpreterError::SolveError(_))
+        ));
+    }
+}
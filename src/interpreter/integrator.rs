@@ -0,0 +1,453 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    node::Node,
+    token::{FuncType, TokenType},
+};
+
+use super::{polynomial::Polynomial, Interpreter, InterpreterError, InterpreterResult};
+
+const SUBSTITUTE: char = '\u{E000}';
+const SAMPLES: [f64; 5] = [0.37, 0.81, 1.23, 1.9, 2.6];
+
+#[derive(Debug, Clone)]
+pub enum IntegratorError {
+    Unsupported(Node),
+    Unverified(Node),
+}
+impl fmt::Display for IntegratorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported(node) => write!(f, "cannot integrate {node}"),
+            Self::Unverified(node) => write!(f, "could not verify the integral of {node}"),
+        }
+    }
+}
+
+fn func(func: FuncType, arg: &Node) -> Node {
+    Node::Func {
+        func,
+        arg: Box::new(arg.clone()),
+    }
+}
+
+fn product(factors: &[Node]) -> Node {
+    Node::Factors(
+        factors
+            .iter()
+            .map(|factor| (TokenType::Mul, factor.clone()))
+            .collect(),
+    )
+}
+
+fn quotient(node: Node, divisor: Node) -> Node {
+    Node::Factors(vec![(TokenType::Mul, node), (TokenType::Div, divisor)])
+}
+
+impl Interpreter {
+    pub fn integrate(&self, node: &Node, var: char) -> InterpreterResult<Node> {
+        self.visit(&self.antiderivative(&self.visit(node, None)?, var)?, None)
+    }
+
+    pub fn indefinite_integral(&self, node: &Node, var: char) -> InterpreterResult<Node> {
+        let node = self.visit(node, None)?;
+        let integral = self.simplify(&self.integrate(&node, var)?);
+        self.verify(&node, &integral, var)?;
+        let mut vars = vec![];
+        node.collect_vars(&mut vars);
+        let constant = ('A'..='Z')
+            .filter(|c| !vars.contains(c))
+            .find(|c| *c >= 'C')
+            .unwrap_or('C');
+        let mut terms = match integral {
+            Node::Terms(terms) => terms,
+            integral => vec![(TokenType::Plus, integral)],
+        };
+        terms.push((TokenType::Plus, Node::Var(constant)));
+        Ok(Node::Terms(terms))
+    }
+
+    fn verify(&self, node: &Node, integral: &Node, var: char) -> InterpreterResult<()> {
+        let derivative = self.differentiate(integral, var, None)?;
+        let mut vars = self.unknowns(node);
+        vars.extend(self.unknowns(integral));
+        vars.push(var);
+        vars.sort_unstable();
+        vars.dedup();
+        for i in 0..SAMPLES.len() {
+            let at = vars
+                .iter()
+                .enumerate()
+                .map(|(j, var)| (*var, SAMPLES[(i + j) % SAMPLES.len()]))
+                .collect();
+            let (Ok(expected), Ok(actual)) =
+                (self.evaluate(node, &at), self.evaluate(&derivative, &at))
+            else {
+                continue;
+            };
+            if expected.is_finite()
+                && actual.is_finite()
+                && (expected - actual).abs() > 1e-6 * expected.abs().max(1f64)
+            {
+                return Err(InterpreterError::IntegratorError(
+                    IntegratorError::Unverified(node.clone()),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn depends_on(&self, node: &Node, var: char) -> bool {
+        self.unknowns(node).contains(&var)
+    }
+
+    fn slope(&self, node: &Node, var: char) -> Option<f64> {
+        let poly = self.polynomial(node, var)?;
+        (poly.degree() == 1).then(|| poly.coefficient(1))
+    }
+
+    fn constant_ratio(&self, num: &Node, den: &Node, var: char) -> Option<f64> {
+        let ratios = SAMPLES
+            .iter()
+            .map(|x| {
+                let at = HashMap::from([(var, *x)]);
+                let ratio = self.evaluate(num, &at).ok()? / self.evaluate(den, &at).ok()?;
+                ratio.is_finite().then_some(ratio)
+            })
+            .collect::<Option<Vec<_>>>()?;
+        ratios
+            .iter()
+            .all(|r| (r - ratios[0]).abs() <= 1e-9 * ratios[0].abs().max(1f64))
+            .then_some(ratios[0])
+    }
+
+    fn antiderivative(&self, node: &Node, var: char) -> InterpreterResult<Node> {
+        if !self.depends_on(node, var) {
+            return Ok(product(&[node.clone(), Node::Var(var)]));
+        }
+        if let Some(poly) = self.polynomial(node, var) {
+            return Ok(poly.integral().to_node(var));
+        }
+        let unsupported =
+            || InterpreterError::IntegratorError(IntegratorError::Unsupported(node.clone()));
+        match node {
+            Node::Terms(terms) => Ok(Node::Terms(InterpreterResult::from_iter(
+                terms
+                    .iter()
+                    .map(|(op, term)| Ok((*op, self.antiderivative(term, var)?))),
+            )?)),
+            Node::Factors(factors) => self.integrate_factors(factors, var),
+            Node::Exponent { base, exponent } if !self.depends_on(exponent, var) => {
+                let n = self.evaluate(exponent, &HashMap::new())?;
+                let poly = self.polynomial(base, var).ok_or_else(unsupported)?;
+                if let [.., c] = poly.coefficients() {
+                    if poly.degree() > 1
+                        && poly.coefficients().iter().filter(|c| **c != 0f64).count() == 1
+                    {
+                        return Ok(product(&[
+                            Node::Num(c.powf(n)),
+                            self.antiderivative(
+                                &Node::Exponent {
+                                    base: Box::new(Node::Var(var)),
+                                    exponent: Box::new(Node::Num(poly.degree() as f64 * n)),
+                                },
+                                var,
+                            )?,
+                        ]));
+                    }
+                }
+                let a = self.slope(base, var).ok_or_else(unsupported)?;
+                Ok(if n == -1f64 {
+                    quotient(func(FuncType::Ln, base), Node::Num(a))
+                } else {
+                    quotient(
+                        Node::Exponent {
+                            base: base.clone(),
+                            exponent: Box::new(Node::Num(n + 1f64)),
+                        },
+                        Node::Num((n + 1f64) * a),
+                    )
+                })
+            }
+            Node::Exponent { base, exponent } if !self.depends_on(base, var) => {
+                let a = self.slope(exponent, var).ok_or_else(unsupported)?;
+                Ok(quotient(
+                    node.clone(),
+                    product(&[Node::Num(a), func(FuncType::Ln, base)]),
+                ))
+            }
+            Node::Func { func: f, arg } => {
+                let a = self.slope(arg, var).ok_or_else(unsupported)?;
+                let negate = |node| product(&[Node::Num(-1f64), node]);
+                let sum = |f1, f2| {
+                    Node::Terms(vec![
+                        (TokenType::Plus, func(f1, arg)),
+                        (TokenType::Plus, func(f2, arg)),
+                    ])
+                };
+                let x_ln_x = Node::Terms(vec![
+                    (
+                        TokenType::Plus,
+                        product(&[*arg.clone(), func(FuncType::Ln, arg)]),
+                    ),
+                    (TokenType::Minus, *arg.clone()),
+                ]);
+                let integral = match f {
+                    FuncType::Sin => negate(func(FuncType::Cos, arg)),
+                    FuncType::Cos => func(FuncType::Sin, arg),
+                    FuncType::Tan => negate(func(FuncType::Ln, &func(FuncType::Cos, arg))),
+                    FuncType::Sec => func(FuncType::Ln, &sum(FuncType::Sec, FuncType::Tan)),
+                    FuncType::Csc => negate(func(FuncType::Ln, &sum(FuncType::Csc, FuncType::Cot))),
+                    FuncType::Cot => func(FuncType::Ln, &func(FuncType::Sin, arg)),
+                    FuncType::Ln => x_ln_x,
+                    FuncType::Log => quotient(x_ln_x, func(FuncType::Ln, &Node::Num(10f64))),
+                    FuncType::Sqrt => product(&[
+                        Node::Num(2f64 / 3f64),
+                        Node::Exponent {
+                            base: arg.clone(),
+                            exponent: Box::new(Node::Num(1.5)),
+                        },
+                    ]),
+                };
+                Ok(quotient(integral, Node::Num(a)))
+            }
+            _ => Err(unsupported()),
+        }
+    }
+
+    fn integrate_factors(
+        &self,
+        factors: &[(TokenType, Node)],
+        var: char,
+    ) -> InterpreterResult<Node> {
+        let unsupported = || {
+            InterpreterError::IntegratorError(IntegratorError::Unsupported(Node::Factors(
+                factors.to_vec(),
+            )))
+        };
+        let (constant, dependent): (Vec<_>, Vec<_>) = factors
+            .iter()
+            .cloned()
+            .partition(|(_, factor)| !self.depends_on(factor, var));
+        let mut merged: Vec<Node> = vec![];
+        for (op, factor) in dependent {
+            let (base, exponent) = match factor {
+                Node::Exponent { base, exponent } => (*base, *exponent),
+                Node::Func {
+                    func: FuncType::Sqrt,
+                    arg,
+                } => (*arg, Node::Num(0.5)),
+                factor => (factor, Node::Num(1f64)),
+            };
+            let exponent = match op {
+                TokenType::Div => Node::Factors(vec![
+                    (TokenType::Mul, Node::Num(-1f64)),
+                    (TokenType::Mul, exponent),
+                ]),
+                _ => exponent,
+            };
+            let same_base = merged.iter_mut().find_map(|node| match node {
+                Node::Exponent { base: b, exponent } if **b == base => Some(exponent),
+                _ => None,
+            });
+            match same_base {
+                Some(existing) => {
+                    **existing = Node::Terms(vec![
+                        (TokenType::Plus, *existing.clone()),
+                        (TokenType::Plus, exponent),
+                    ])
+                }
+                None => merged.push(Node::Exponent {
+                    base: Box::new(base),
+                    exponent: Box::new(exponent),
+                }),
+            }
+        }
+        let merged = InterpreterResult::<Vec<Node>>::from_iter(
+            merged.iter().map(|node| self.visit(node, None)),
+        )?;
+        let integral = match &merged[..] {
+            [node] => self.antiderivative(node, var)?,
+            [a, b] => self
+                .exponential_trig(a, b, var)
+                .or_else(|| self.exponential_trig(b, a, var))
+                .map(Ok)
+                .or_else(|| {
+                    self.by_parts(a, b, var)
+                        .or_else(|| self.by_parts(b, a, var))
+                })
+                .or_else(|| self.by_substitution(&merged, var))
+                .ok_or_else(unsupported)??,
+            _ => self
+                .by_substitution(&merged, var)
+                .ok_or_else(unsupported)??,
+        };
+        let mut result = constant;
+        result.push((TokenType::Mul, integral));
+        Ok(Node::Factors(result))
+    }
+
+    fn exponential_trig(&self, exponential: &Node, trig: &Node, var: char) -> Option<Node> {
+        let (Node::Exponent { base, exponent }, Node::Func { func: f, arg }) = (exponential, trig)
+        else {
+            return None;
+        };
+        if self.depends_on(base, var) {
+            return None;
+        }
+        let a = self.slope(exponent, var)? * self.evaluate(base, &HashMap::new()).ok()?.ln();
+        let c = self.slope(arg, var)?;
+        let (same, other, sign) = match f {
+            FuncType::Sin => (FuncType::Sin, FuncType::Cos, TokenType::Minus),
+            FuncType::Cos => (FuncType::Cos, FuncType::Sin, TokenType::Plus),
+            _ => return None,
+        };
+        Some(quotient(
+            product(&[
+                exponential.clone(),
+                Node::Terms(vec![
+                    (TokenType::Plus, product(&[Node::Num(a), func(same, arg)])),
+                    (sign, product(&[Node::Num(c), func(other, arg)])),
+                ]),
+            ]),
+            Node::Num(a * a + c * c),
+        ))
+    }
+
+    fn by_parts(&self, poly: &Node, other: &Node, var: char) -> Option<InterpreterResult<Node>> {
+        let poly = self.polynomial(poly, var)?;
+        if let Node::Func {
+            func: FuncType::Ln | FuncType::Log,
+            ..
+        } = other
+        {
+            let integral = poly.integral().to_node(var);
+            let derivative = self.differentiate(other, var, None).ok()?;
+            let rest = self.polynomial(
+                &self.simplify(&product(&[integral.clone(), derivative])),
+                var,
+            )?;
+            return Some(Ok(Node::Terms(vec![
+                (TokenType::Plus, product(&[integral, other.clone()])),
+                (TokenType::Minus, rest.integral().to_node(var)),
+            ])));
+        }
+        let integral = self.integrate(other, var).ok()?;
+        let derivative = (1..=poly.degree())
+            .map(|i| poly.coefficient(i) * i as f64)
+            .collect();
+        let derivative = Polynomial::new(derivative).to_node(var);
+        Some((|| {
+            let rest = self.integrate(&product(&[derivative, integral.clone()]), var)?;
+            Ok(Node::Terms(vec![
+                (TokenType::Plus, product(&[poly.to_node(var), integral])),
+                (TokenType::Minus, rest),
+            ]))
+        })())
+    }
+
+    fn by_substitution(&self, factors: &[Node], var: char) -> Option<InterpreterResult<Node>> {
+        for (i, factor) in factors.iter().enumerate() {
+            let inner = match factor {
+                Node::Exponent { base, exponent } if !self.depends_on(base, var) => {
+                    vec![exponent.as_ref()]
+                }
+                Node::Exponent { base, exponent } if !self.depends_on(exponent, var) => {
+                    vec![base.as_ref(), factor]
+                }
+                Node::Func { arg, .. } => vec![arg.as_ref(), factor],
+                _ => vec![factor],
+            };
+            let rest = product(
+                &factors
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(_, node)| node.clone())
+                    .collect::<Vec<_>>(),
+            );
+            for u in inner {
+                let Ok(du) = self.differentiate(u, var, None) else {
+                    continue;
+                };
+                let Some(c) = self.constant_ratio(&rest, &du, var) else {
+                    continue;
+                };
+                let outer = factor.replace(&|node| (node == u).then_some(Node::Var(SUBSTITUTE)));
+                if self.depends_on(&outer, var) {
+                    continue;
+                }
+                if let Ok(integral) = self.integrate(&outer, SUBSTITUTE) {
+                    return Some(Ok(product(&[
+                        Node::Num(c),
+                        integral.substitute(SUBSTITUTE, u),
+                    ])));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::IntegratorError;
+    use crate::interpreter::{parse, run, Interpreter, InterpreterError};
+
+    fn assert_antiderivative(integrand: &str) {
+        let integral = run(&format!("integrate({integrand}, x)")).unwrap();
+        let (interpreter, f) = (Interpreter::new(), parse(integrand));
+        let at = |node, x| {
+            interpreter
+                .evaluate(node, &HashMap::from([('x', x), ('C', 0f64)]))
+                .unwrap()
+        };
+        let h = 1e-5;
+        for x in [0.7, 1.3, 2.1] {
+            let slope = (at(&integral, x + h) - at(&integral, x - h)) / (2f64 * h);
+            let expected = at(&f, x);
+            assert!(
+                (slope - expected).abs() < 1e-6 * expected.abs().max(1f64),
+                "{integrand}: {integral}"
+            );
+        }
+    }
+
+    #[test]
+    fn polynomials_and_linear_arguments() {
+        for integrand in ["3x^2+2x", "e^(2x+1)", "1/(2x+3)", "cos(3x-1)"] {
+            assert_antiderivative(integrand);
+        }
+    }
+
+    #[test]
+    fn integration_by_parts() {
+        for integrand in ["x*e^x", "x*ln(x)", "x^2*cos(x)", "ln(x)"] {
+            assert_antiderivative(integrand);
+        }
+    }
+
+    #[test]
+    fn substitution() {
+        for integrand in ["2x*e^(x^2)", "cos(x)*sin(x)^2"] {
+            assert_antiderivative(integrand);
+        }
+    }
+
+    #[test]
+    fn exponential_times_trig() {
+        assert_antiderivative("e^x*sin(x)");
+    }
+
+    #[test]
+    fn unsupported_integrand() {
+        assert!(matches!(
+            run("integrate(e^(x^2), x)"),
+            Err(InterpreterError::IntegratorError(
+                IntegratorError::Unsupported(_)
+            ))
+        ));
+    }
+}
@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+
+use crate::{
+    node::Node,
+    token::{CommandType, FuncType, TokenType},
+};
+
+use super::{Interpreter, InterpreterError, InterpreterResult};
+
+const DEPTH: u32 = 8;
+const ROWS: usize = 24;
+
+#[derive(Debug, Clone, Copy)]
+struct Target {
+    var: char,
+    value: f64,
+    side: f64,
+}
+
+fn reciprocal_of(x: f64) -> f64 {
+    if x.abs() < 1e-12 {
+        f64::INFINITY
+    } else {
+        1f64 / x
+    }
+}
+
+fn apply(func: &FuncType, x: f64) -> f64 {
+    match func {
+        FuncType::Sin => x.sin(),
+        FuncType::Cos => x.cos(),
+        FuncType::Tan => x.sin() * reciprocal_of(x.cos()),
+        FuncType::Csc => reciprocal_of(x.sin()),
+        FuncType::Sec => reciprocal_of(x.cos()),
+        FuncType::Cot => x.cos() * reciprocal_of(x.sin()),
+        FuncType::Ln => x.ln(),
+        FuncType::Log => x.log10(),
+        FuncType::Sqrt => x.sqrt(),
+    }
+}
+
+fn product(nodes: &[(Node, f64)]) -> (Node, f64) {
+    (
+        Node::Factors(
+            nodes
+                .iter()
+                .map(|(node, _)| (TokenType::Mul, node.clone()))
+                .chain(
+                    nodes
+                        .is_empty()
+                        .then_some((TokenType::Mul, Node::Num(1f64))),
+                )
+                .collect(),
+        ),
+        nodes.iter().map(|(_, limit)| limit).product(),
+    )
+}
+
+fn reciprocal((node, limit): (Node, f64)) -> (Node, f64) {
+    (
+        Node::Factors(vec![
+            (TokenType::Mul, Node::Num(1f64)),
+            (TokenType::Div, node),
+        ]),
+        1f64 / limit,
+    )
+}
+
+fn balance(from: &mut Vec<(Node, f64)>, to: &mut Vec<(Node, f64)>) {
+    if product(from).1.is_nan() {
+        let (zeros, rest) = from.drain(..).partition(|(_, limit)| *limit == 0f64);
+        *from = rest;
+        to.extend(zeros.into_iter().map(reciprocal));
+    }
+}
+
+impl Interpreter {
+    fn point(&self, f: &Node, target: Target, t: f64) -> InterpreterResult<f64> {
+        let x = if target.value.is_infinite() {
+            target.value.signum() / t
+        } else {
+            target.value + target.side * t * target.value.abs().max(1f64)
+        };
+        self.evaluate(f, &HashMap::from([(target.var, x)]))
+    }
+
+    fn numeric_limit(&self, f: &Node, target: Target) -> InterpreterResult<f64> {
+        let mut values = vec![];
+        let mut previous: Vec<f64> = vec![];
+        let (mut best, mut error) = (f64::NAN, f64::INFINITY);
+        for i in 0..ROWS {
+            let value = self.point(f, target, 0.5f64.powi(i as i32 + 1))?;
+            values.push(value);
+            let mut row = vec![value];
+            for j in 1..=i {
+                let factor = 2f64.powi(j as i32);
+                let extrapolated = row[j - 1] + (row[j - 1] - previous[j - 1]) / (factor - 1f64);
+                let estimate = (extrapolated - row[j - 1])
+                    .abs()
+                    .max((extrapolated - previous[j - 1]).abs());
+                row.push(extrapolated);
+                if estimate <= error {
+                    (best, error) = (extrapolated, estimate);
+                }
+            }
+            previous = row;
+        }
+        let [.., a, b, c] = values[..] else {
+            unreachable!()
+        };
+        if c.abs() > 1e8 && c.abs() > b.abs() && b.abs() > a.abs() && a * c > 0f64 {
+            return Ok(f64::INFINITY.copysign(c));
+        }
+        if error > 1e-6 * best.abs().max(1f64) || (best - c).abs() > 1e-4 * best.abs().max(1f64) {
+            (best, error) = (c, (c - b).abs());
+        }
+        if error > 1e-6 * best.abs().max(1f64) {
+            return Err(InterpreterError::SolveError(String::from(
+                "Limit does not exist",
+            )));
+        }
+        Ok(if best.abs() <= 10f64 * error.max((c - b).abs()) {
+            0f64
+        } else {
+            best
+        })
+    }
+
+    fn limit_of(&self, node: &Node, target: Target, depth: u32) -> InterpreterResult<f64> {
+        let limit = match node {
+            Node::Num(n) => return Ok(*n),
+            Node::Var(var) if *var == target.var => return Ok(target.value),
+            Node::Var(_) => f64::NAN,
+            Node::Func { func, arg } => apply(func, self.limit_of(arg, target, depth)?),
+            Node::Exponent { base, exponent } => {
+                let b = self.limit_of(base, target, depth)?;
+                let e = self.limit_of(exponent, target, depth)?;
+                if (b == 1f64 && e.is_infinite())
+                    || (b == 0f64 && e == 0f64)
+                    || (b.is_infinite() && e == 0f64)
+                {
+                    self.limit_of(
+                        &Node::Factors(vec![
+                            (TokenType::Mul, *exponent.clone()),
+                            (
+                                TokenType::Mul,
+                                Node::Func {
+                                    func: FuncType::Ln,
+                                    arg: base.clone(),
+                                },
+                            ),
+                        ]),
+                        target,
+                        depth,
+                    )?
+                    .exp()
+                } else {
+                    b.powf(e)
+                }
+            }
+            Node::Terms(terms) => terms.iter().try_fold(0f64, |acc, (op, term)| {
+                let limit = self.limit_of(term, target, depth)?;
+                Ok(match op {
+                    TokenType::Minus => acc - limit,
+                    _ => acc + limit,
+                })
+            })?,
+            Node::Factors(factors) => self.limit_of_factors(factors, target, depth)?,
+            Node::Derivative { .. } | Node::Command { .. } => {
+                self.limit_of(&self.visit(node, None)?, target, depth)?
+            }
+            _ => return Err(InterpreterError::InvalidArguments(CommandType::Lim)),
+        };
+        if limit.is_infinite() && target.value.is_finite() {
+            if let Ok(near) = self.point(node, target, 1e-9) {
+                if near != 0f64 && !near.is_nan() {
+                    return Ok(f64::INFINITY.copysign(near));
+                }
+            }
+        }
+        Ok(limit)
+    }
+
+    fn limit_of_factors(
+        &self,
+        factors: &[(TokenType, Node)],
+        target: Target,
+        depth: u32,
+    ) -> InterpreterResult<f64> {
+        let (mut num, mut den) = (vec![], vec![]);
+        for (op, factor) in factors {
+            let limit = self.limit_of(factor, target, depth)?;
+            match op {
+                TokenType::Div => den.push((factor.clone(), limit)),
+                _ => num.push((factor.clone(), limit)),
+            }
+        }
+        let limit = product(&num).1 / product(&den).1;
+        if !limit.is_nan() || depth == 0 {
+            return Ok(limit);
+        }
+        balance(&mut num, &mut den);
+        balance(&mut den, &mut num);
+        let ((p, top), (q, bottom)) = (product(&num), product(&den));
+        if !((top == 0f64 && bottom == 0f64) || (top.is_infinite() && bottom.is_infinite())) {
+            return Ok(f64::NAN);
+        }
+        let derivative = |node: &Node| -> InterpreterResult<Node> {
+            Ok(self.simplify(&self.differentiate(node, target.var, None)?))
+        };
+        self.limit_of(
+            &self.simplify(&Node::Factors(vec![
+                (TokenType::Mul, derivative(&p)?),
+                (TokenType::Div, derivative(&q)?),
+            ])),
+            target,
+            depth - 1,
+        )
+    }
+
+    fn defined_near(&self, f: &Node, target: Target) -> bool {
+        [1e-3, 1e-6, 1e-9]
+            .into_iter()
+            .any(|t| self.point(f, target, t).is_ok_and(|value| !value.is_nan()))
+    }
+
+    fn one_sided(&self, f: &Node, target: Target) -> InterpreterResult<f64> {
+        if !self.defined_near(f, target) {
+            return Err(InterpreterError::Undefined);
+        }
+        let limit = self.limit_of(f, target, DEPTH)?;
+        if limit.is_nan() {
+            self.numeric_limit(f, target)
+        } else {
+            Ok(limit)
+        }
+    }
+
+    pub fn limit(&self, expr: &Node, approach: &Node) -> InterpreterResult<Node> {
+        let Node::Approach { var, target, side } = approach else {
+            return Err(InterpreterError::InvalidArguments(CommandType::Lim));
+        };
+        let value = self.evaluate(target, &HashMap::new())?;
+        let f = self.visit(expr, None)?;
+        let sides = match side {
+            Some(TokenType::Plus) => vec![1f64],
+            Some(TokenType::Minus) => vec![-1f64],
+            _ if value.is_infinite() => vec![-value.signum()],
+            _ => vec![1f64, -1f64],
+        };
+        let limits = sides
+            .into_iter()
+            .map(|side| {
+                self.one_sided(
+                    &f,
+                    Target {
+                        var: *var,
+                        value,
+                        side,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        if limits.iter().any(Result::is_ok)
+            && limits
+                .iter()
+                .any(|limit| matches!(limit, Err(InterpreterError::Undefined)))
+        {
+            return Err(InterpreterError::SolveError(format!(
+                "Limit does not exist, undefined on one side of {var} = {value}"
+            )));
+        }
+        let limits = InterpreterResult::<Vec<f64>>::from_iter(limits)?;
+        let limit = limits[0];
+        if limits.iter().any(|other| {
+            other != &limit
+                && (other.is_infinite()
+                    || limit.is_infinite()
+                    || (other - limit).abs() > 1e-9 * limit.abs().max(1f64))
+        }) {
+            return Err(InterpreterError::SolveError(String::from(
+                "Limit does not exist",
+            )));
+        }
+        if limit.is_nan() {
+            Err(InterpreterError::Undefined)
+        } else if limit == f64::INFINITY {
+            Err(InterpreterError::Infinity)
+        } else if limit == f64::NEG_INFINITY {
+            Err(InterpreterError::NegInfinity)
+        } else {
+            Ok(Node::Num(limit))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interpreter::{run, InterpreterError},
+        node::Node,
+    };
+
+    fn limit(line: &str) -> f64 {
+        match run(line) {
+            Ok(Node::Num(value)) => value,
+            other => panic!("{line}: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn known_limits() {
+        assert_eq!(limit("lim(sin(x)/x, x -> 0)"), 1f64);
+        assert!((limit("lim((1+1/x)^x, x -> ∞)") - std::f64::consts::E).abs() < 1e-9);
+        assert!((limit("lim((1-cos(x))/x^2, x -> 0)") - 0.5).abs() < 1e-9);
+        assert_eq!(limit("lim(x*ln(x), x -> 0+)"), 0f64);
+    }
+
+    #[test]
+    fn infinite_limits() {
+        assert!(matches!(
+            run("lim(1/x^2, x -> 0)"),
+            Err(InterpreterError::Infinity)
+        ));
+        assert!(matches!(
+            run("lim(ln(x), x -> 0+)"),
+            Err(InterpreterError::NegInfinity)
+        ));
+    }
+
+    #[test]
+    fn side_where_undefined_is_not_ignored() {
+        for line in [
+            "lim(ln(x), x -> 0)",
+            "lim(sqrt(x), x -> 0)",
+            "lim(1/x, x -> 0)",
+        ] {
+            assert!(
+                matches!(run(line), Err(InterpreterError::SolveError(_))),
+                "{line}"
+            );
+        }
+        assert_eq!(limit("lim(sqrt(x), x -> 0+)"), 0f64);
+        assert!(matches!(
+            run("lim(ln(x), x -> -∞)"),
+            Err(InterpreterError::Undefined)
+        ));
+    }
+}
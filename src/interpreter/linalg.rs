@@ -0,0 +1,109 @@
+pub struct Lu {
+    lu: Vec<Vec<f64>>,
+    perm: Vec<usize>,
+}
+impl Lu {
+    pub fn decompose(matrix: &[Vec<f64>]) -> Option<Self> {
+        let n = matrix.len();
+        let mut lu = matrix.to_vec();
+        let mut perm = (0..n).collect::<Vec<_>>();
+        let scale = lu
+            .iter()
+            .flatten()
+            .fold(0f64, |acc, v| acc.max(v.abs()))
+            .max(1f64);
+        for k in 0..n {
+            let pivot = (k..n)
+                .max_by(|&a, &b| lu[a][k].abs().total_cmp(&lu[b][k].abs()))
+                .expect("range is not empty");
+            if lu[pivot][k].abs() <= scale * 1e-12 {
+                return None;
+            }
+            lu.swap(k, pivot);
+            perm.swap(k, pivot);
+            let (upper, lower) = lu.split_at_mut(k + 1);
+            let pivot_row = &upper[k];
+            for row in lower {
+                let factor = row[k] / pivot_row[k];
+                row[k] = factor;
+                for (v, p) in row.iter_mut().zip(pivot_row).skip(k + 1) {
+                    *v -= factor * p;
+                }
+            }
+        }
+        Some(Self { lu, perm })
+    }
+
+    pub fn solve(&self, rhs: &[f64]) -> Vec<f64> {
+        let n = self.lu.len();
+        let mut x = self.perm.iter().map(|&i| rhs[i]).collect::<Vec<_>>();
+        for i in 0..n {
+            for j in 0..i {
+                x[i] -= self.lu[i][j] * x[j];
+            }
+        }
+        for i in (0..n).rev() {
+            for j in i + 1..n {
+                x[i] -= self.lu[i][j] * x[j];
+            }
+            x[i] /= self.lu[i][i];
+        }
+        x
+    }
+}
+
+pub fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+pub fn norm(v: &[f64]) -> f64 {
+    dot(v, v).sqrt()
+}
+
+pub fn broyden_update(matrix: &mut [Vec<f64>], dx: &[f64], df: &[f64]) {
+    let dx_norm = dx.iter().map(|x| x * x).sum::<f64>();
+    if dx_norm == 0f64 {
+        return;
+    }
+    for (row, df) in matrix.iter_mut().zip(df) {
+        let predicted = row.iter().zip(dx).map(|(a, x)| a * x).sum::<f64>();
+        let correction = (df - predicted) / dx_norm;
+        for (a, x) in row.iter_mut().zip(dx) {
+            *a += correction * x;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{broyden_update, Lu};
+
+    #[test]
+    fn lu_solves_with_pivoting() {
+        let matrix = vec![
+            vec![0f64, 2f64, 1f64],
+            vec![1f64, 1f64, 1f64],
+            vec![2f64, 1f64, 3f64],
+        ];
+        let x = Lu::decompose(&matrix).unwrap().solve(&[7f64, 6f64, 13f64]);
+        for (x, expected) in x.iter().zip([1f64, 2f64, 3f64]) {
+            assert!((x - expected).abs() < 1e-12, "{x}");
+        }
+    }
+
+    #[test]
+    fn singular_matrix_has_no_decomposition() {
+        assert!(Lu::decompose(&[vec![1f64, 2f64], vec![2f64, 4f64]]).is_none());
+    }
+
+    #[test]
+    fn broyden_update_satisfies_secant_condition() {
+        let mut matrix = vec![vec![1f64, 0f64], vec![0f64, 1f64]];
+        let (dx, df) = ([0.5, -0.25], [2f64, 1f64]);
+        broyden_update(&mut matrix, &dx, &df);
+        for (row, df) in matrix.iter().zip(df) {
+            let product = row.iter().zip(&dx).map(|(a, b)| a * b).sum::<f64>();
+            assert!((product - df).abs() < 1e-12);
+        }
+    }
+}
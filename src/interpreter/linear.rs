@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+
+use crate::{node::Node, token::TokenType};
+
+use super::{rational::Rational, Interpreter, InterpreterError, InterpreterResult};
+
+trait Scalar: Copy {
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn sub(self, rhs: Self) -> Option<Self>;
+    fn mul(self, rhs: Self) -> Option<Self>;
+    fn div(self, rhs: Self) -> Option<Self>;
+    fn is_zero(self) -> bool;
+    fn magnitude(self) -> f64;
+    fn signum(self) -> f64;
+    fn to_node(self) -> Node;
+}
+impl Scalar for Rational {
+    fn zero() -> Self {
+        Rational::new(0, 1)
+    }
+
+    fn one() -> Self {
+        Rational::new(1, 1)
+    }
+
+    fn sub(self, rhs: Self) -> Option<Self> {
+        self.checked_sub(rhs)
+    }
+
+    fn mul(self, rhs: Self) -> Option<Self> {
+        self.checked_mul(rhs)
+    }
+
+    fn div(self, rhs: Self) -> Option<Self> {
+        self.checked_div(rhs)
+    }
+
+    fn is_zero(self) -> bool {
+        Rational::is_zero(self)
+    }
+
+    fn magnitude(self) -> f64 {
+        if self.is_zero() {
+            0f64
+        } else {
+            1f64
+        }
+    }
+
+    fn signum(self) -> f64 {
+        Rational::signum(self)
+    }
+
+    fn to_node(self) -> Node {
+        Rational::to_node(self)
+    }
+}
+impl Scalar for f64 {
+    fn zero() -> Self {
+        0f64
+    }
+
+    fn one() -> Self {
+        1f64
+    }
+
+    fn sub(self, rhs: Self) -> Option<Self> {
+        Some(self - rhs)
+    }
+
+    fn mul(self, rhs: Self) -> Option<Self> {
+        Some(self * rhs)
+    }
+
+    fn div(self, rhs: Self) -> Option<Self> {
+        Some(self / rhs)
+    }
+
+    fn is_zero(self) -> bool {
+        self.abs() < 1e-12
+    }
+
+    fn magnitude(self) -> f64 {
+        self.abs()
+    }
+
+    fn signum(self) -> f64 {
+        f64::signum(self)
+    }
+
+    fn to_node(self) -> Node {
+        Node::Num(self)
+    }
+}
+
+struct Pivot<S> {
+    var: usize,
+    constant: S,
+    free: Vec<(usize, S)>,
+}
+
+enum LinearSolution<S> {
+    Inconsistent,
+    Solved(Vec<Pivot<S>>),
+}
+
+fn row_reduce<S: Scalar>(mut matrix: Vec<Vec<S>>, vars: usize) -> Option<LinearSolution<S>> {
+    let mut pivots = vec![];
+    let mut row = 0;
+    for col in 0..vars {
+        let Some(pivot) = (row..matrix.len())
+            .filter(|&r| !matrix[r][col].is_zero())
+            .max_by(|&a, &b| {
+                matrix[a][col]
+                    .magnitude()
+                    .total_cmp(&matrix[b][col].magnitude())
+            })
+        else {
+            continue;
+        };
+        matrix.swap(row, pivot);
+        let lead = matrix[row][col];
+        for v in matrix[row].iter_mut() {
+            *v = v.div(lead)?;
+        }
+        let pivot_row = matrix[row].clone();
+        for (r, other) in matrix.iter_mut().enumerate() {
+            if r != row && !other[col].is_zero() {
+                let factor = other[col];
+                for (v, p) in other.iter_mut().zip(&pivot_row) {
+                    *v = v.sub(factor.mul(*p)?)?;
+                }
+            }
+        }
+        pivots.push(col);
+        row += 1;
+    }
+    if matrix[row..].iter().any(|r| !r[vars].is_zero()) {
+        return Some(LinearSolution::Inconsistent);
+    }
+    Some(LinearSolution::Solved(
+        pivots
+            .iter()
+            .enumerate()
+            .map(|(r, &col)| Pivot {
+                var: col,
+                constant: matrix[r][vars],
+                free: (0..vars)
+                    .filter(|c| !pivots.contains(c) && !matrix[r][*c].is_zero())
+                    .map(|c| (c, matrix[r][c]))
+                    .collect(),
+            })
+            .collect(),
+    ))
+}
+
+fn solution_nodes<S: Scalar>(
+    solution: LinearSolution<S>,
+    vars: &[char],
+) -> InterpreterResult<Node> {
+    let LinearSolution::Solved(rows) = solution else {
+        return Err(InterpreterError::SolveError(String::from(
+            "Inconsistent system",
+        )));
+    };
+    Ok(Node::System(
+        rows.into_iter()
+            .map(
+                |Pivot {
+                     var,
+                     constant,
+                     free,
+                 }| {
+                    let mut terms = vec![];
+                    if !constant.is_zero() || free.is_empty() {
+                        terms.push((TokenType::Plus, constant.to_node()));
+                    }
+                    for (c, coef) in free {
+                        let (sign, magnitude) = if coef.signum() < 0f64 {
+                            (TokenType::Plus, S::zero().sub(coef).expect("negation fits"))
+                        } else {
+                            (TokenType::Minus, coef)
+                        };
+                        terms.push((
+                            sign,
+                            if magnitude.sub(S::one()).is_some_and(S::is_zero) {
+                                Node::Var(vars[c])
+                            } else {
+                                Node::Factors(vec![
+                                    (TokenType::Mul, magnitude.to_node()),
+                                    (TokenType::Mul, Node::Var(vars[c])),
+                                ])
+                            },
+                        ));
+                    }
+                    Node::Equation {
+                        lhs: Box::new(Node::Var(vars[var])),
+                        rhs: Box::new(match &terms[..] {
+                            [(TokenType::Plus, node)] => node.clone(),
+                            _ => Node::Terms(terms),
+                        }),
+                    }
+                },
+            )
+            .collect(),
+    ))
+}
+
+impl Interpreter {
+    pub fn solve_linear(&self, eqs: &[Node], vars: &[char]) -> InterpreterResult<Option<Node>> {
+        let origin = vars
+            .iter()
+            .map(|var| (*var, 0f64))
+            .collect::<HashMap<_, _>>();
+        let mut matrix = vec![];
+        for eq in eqs {
+            let f = self.move_equation(eq)?;
+            let mut row = vec![];
+            for var in vars {
+                match self.visit(&self.differentiate(&f, *var, None)?, None)? {
+                    Node::Num(num) => row.push(num),
+                    _ => return Ok(None),
+                }
+            }
+            match self.visit(&f, Some(&origin))? {
+                Node::Num(num) => row.push(-num),
+                _ => return Ok(None),
+            }
+            matrix.push(row);
+        }
+        let exact = matrix
+            .iter()
+            .map(|row| row.iter().map(|n| Rational::from_f64(*n)).collect())
+            .collect::<Option<Vec<Vec<_>>>>();
+        if let Some(solution) = exact.and_then(|matrix| row_reduce(matrix, vars.len())) {
+            return solution_nodes(solution, vars).map(Some);
+        }
+        let solution = row_reduce(matrix, vars.len()).expect("float arithmetic cannot overflow");
+        solution_nodes(solution, vars).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::{run, InterpreterError};
+
+    fn solve(line: &str) -> String {
+        run(line).unwrap().to_string()
+    }
+
+    #[test]
+    fn exact_fractions() {
+        assert_eq!(solve("3x+y=1, x-y=0"), "x = 1/4, y = 1/4");
+        assert_eq!(solve("x+2y+z=6, y-z=0, x+z=2"), "x = 0, y = 2, z = 2");
+    }
+
+    #[test]
+    fn underdetermined_system() {
+        assert_eq!(solve("x+y=1, 2x+2y=2"), "x = 1-y");
+    }
+
+    #[test]
+    fn inconsistent_system() {
+        assert!(matches!(
+            run("x+y=1, x+y=2"),
+            Err(InterpreterError::SolveError(_))
+        ));
+    }
+}
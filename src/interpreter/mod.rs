@@ -1,11 +1,36 @@
 use std::{collections::HashMap, f64, fmt};
 
-use crate::node::Node;
+use crate::{node::Node, token::CommandType};
 
-use self::differentiator::DifferentiatorError;
+use self::{differentiator::DifferentiatorError, integrator::IntegratorError, steps::Trace};
+pub use self::{newton::SystemMethod, ode::OdeMethod, quadrature::QuadratureMethod};
 
+mod autodiff;
+mod calculus;
+mod complex;
 mod differentiator;
+mod factor;
+mod fit;
+mod inequality;
+mod integrator;
+mod limit;
+mod linalg;
+mod linear;
 mod newton;
+mod numeric;
+mod ode;
+mod optimize;
+mod polynomial;
+mod quadrature;
+mod rational;
+mod roots;
+mod simplify;
+mod solve;
+mod steps;
+mod summation;
+mod symbolic_ode;
+mod taylor;
+mod visit_command;
 mod visit_exponent;
 mod visit_factors;
 mod visit_func;
@@ -17,7 +42,10 @@ pub enum InterpreterError {
     Infinity,
     NegInfinity,
     DifferentiatorError(DifferentiatorError),
+    IntegratorError(IntegratorError),
     SolveError(String),
+    SingularJacobian(Vec<(char, f64)>),
+    InvalidArguments(CommandType),
 }
 impl fmt::Display for InterpreterError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -26,7 +54,20 @@ impl fmt::Display for InterpreterError {
             Self::Infinity => "infinity",
             Self::NegInfinity => "-infinity",
             Self::DifferentiatorError(err) => return err.fmt(f),
+            Self::IntegratorError(err) => return err.fmt(f),
             Self::SolveError(s) => s,
+            Self::InvalidArguments(command) => return write!(f, "invalid arguments to {command}"),
+            Self::SingularJacobian(point) => {
+                return write!(
+                    f,
+                    "singular Jacobian at ({})",
+                    point
+                        .iter()
+                        .map(|(var, val)| format!("{var} = {val}"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            }
         })
     }
 }
@@ -37,33 +78,130 @@ fn is_int(n: f64) -> bool {
     n == (n as u32) as f64
 }
 
+fn estimate(value: f64, error: f64) -> Node {
+    if error == 0f64 {
+        Node::Num(value)
+    } else {
+        Node::Estimate { value, error }
+    }
+}
+
 pub struct Interpreter {
     table: HashMap<char, f64>,
+    complex: bool,
+    ode_method: OdeMethod,
+    quadrature_method: QuadratureMethod,
+    check: bool,
+    trace: Option<Trace>,
 }
 impl Interpreter {
     pub fn new() -> Self {
         let mut table = HashMap::new();
         table.insert('π', f64::consts::PI);
         table.insert('e', f64::consts::E);
-        Self { table }
+        Self {
+            table,
+            complex: false,
+            ode_method: OdeMethod::DormandPrince,
+            quadrature_method: QuadratureMethod::GaussKronrod,
+            check: false,
+            trace: None,
+        }
+    }
+
+    pub fn set_complex(&mut self, complex: bool) {
+        self.complex = complex;
+    }
+
+    pub fn set_ode_method(&mut self, method: OdeMethod) {
+        self.ode_method = method;
+    }
+
+    pub fn set_quadrature_method(&mut self, method: QuadratureMethod) {
+        self.quadrature_method = method;
+    }
+
+    pub fn set_check(&mut self, check: bool) {
+        self.check = check;
+    }
+
+    pub fn set_steps(&mut self, steps: bool) {
+        self.trace = steps.then(Trace::default);
+    }
+
+    pub fn unknowns(&self, node: &Node) -> Vec<char> {
+        let mut vars = vec![];
+        node.collect_vars(&mut vars);
+        vars.retain(|var| !self.table.contains_key(var));
+        vars.sort_unstable();
+        vars
     }
 
     pub fn visit(&self, node: &Node, ext: Option<&HashMap<char, f64>>) -> InterpreterResult<Node> {
         Ok(match node {
-            Node::Num(_) => node.clone(),
+            Node::Num(_)
+            | Node::Bool(_)
+            | Node::Str(_)
+            | Node::Set { .. }
+            | Node::Table { .. }
+            | Node::Fit { .. }
+            | Node::Estimate { .. } => node.clone(),
             Node::Var(var) => self
                 .table
                 .get(var)
                 .or_else(|| ext.and_then(|t| t.get(var)))
                 .map_or_else(|| node.clone(), |val| Node::Num(*val)),
-            Node::Func { func, arg } => self.visit_func(func, arg, ext)?,
-            Node::Exponent { base, exponent } => self.visit_exponent(base, exponent, ext)?,
-            Node::Factors(factors) => self.visit_factors(factors, ext)?,
-            Node::Terms(terms) => self.visit_terms(terms, ext)?,
-            Node::Derivative { derivative, var } => {
-                self.visit(&self.differentiate(derivative, *var, ext)?, ext)?
+            Node::Func { func, arg } => self.reduce_imaginary(self.visit_func(func, arg, ext)?),
+            Node::Exponent { base, exponent } => {
+                self.reduce_imaginary(self.visit_exponent(base, exponent, ext)?)
+            }
+            Node::Factors(factors) => self.reduce_imaginary(self.visit_factors(factors, ext)?),
+            Node::Terms(terms) => self.reduce_imaginary(self.visit_terms(terms, ext)?),
+            Node::Derivative { derivative, vars } => {
+                let derivative = self.simplify(&self.derive(derivative, vars, None)?);
+                if ext.is_some() {
+                    self.visit(&derivative, ext)?
+                } else {
+                    derivative
+                }
+            }
+            Node::Prime { var, wrt, .. } => {
+                return Err(InterpreterError::SolveError(match wrt {
+                    Some(wrt) => format!("{var} is not a function of {wrt}"),
+                    None => format!("Cannot determine the variable of {node}, write {node}(x)"),
+                }))
             }
-            Node::Equation { .. } => Node::Num(self.solve_equation(node, 0f64)?),
+            Node::Equation { .. } if self.is_ode(std::slice::from_ref(node)) => {
+                self.solve_ode(std::slice::from_ref(node))?
+            }
+            Node::Equation { .. } => self.solve(node)?,
+            Node::Vector(nodes) => Node::Vector(InterpreterResult::from_iter(
+                nodes.iter().map(|node| self.visit(node, ext)),
+            )?),
+            Node::Relation { lhs, op, rhs } => self.visit_relation(lhs, *op, rhs, ext)?,
+            Node::Approach { var, target, side } => Node::Approach {
+                var: *var,
+                target: Box::new(self.visit(target, ext)?),
+                side: *side,
+            },
+            Node::Range { start, end } => Node::Range {
+                start: Box::new(self.visit(start, ext)?),
+                end: Box::new(self.visit(end, ext)?),
+            },
+            Node::System(eqs) if self.is_ode(eqs) => self.solve_ode(eqs)?,
+            Node::System(eqs) => self.solve_equations(eqs)?,
+            Node::Command { command, args } => self.visit_command(*command, args, ext)?,
         })
     }
 }
+
+#[cfg(test)]
+pub fn parse(line: &str) -> Node {
+    let tokens = crate::tokenizer::Tokenizer::new(line).tokenize().unwrap();
+    crate::parser::Parser::new(&tokens).parse().unwrap()
+}
+
+#[cfg(test)]
+pub fn run(line: &str) -> InterpreterResult<Node> {
+    Interpreter::new().visit(&parse(line), None)
+}
@@ -2,10 +2,16 @@ use std::collections::HashMap;
 
 use crate::{node::Node, token::TokenType};
 
-use super::{Interpreter, InterpreterError, InterpreterResult};
+use super::{linalg, Interpreter, InterpreterError, InterpreterResult};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemMethod {
+    Newton,
+    Broyden,
+}
 
 impl Interpreter {
-    fn move_equation(&self, eq: &Node) -> InterpreterResult<Node> {
+    pub fn move_equation(&self, eq: &Node) -> InterpreterResult<Node> {
         if let Node::Equation { lhs, rhs } = eq {
             Ok(Node::Terms(vec![
                 (TokenType::Plus, *lhs.clone()),
@@ -18,174 +24,199 @@ impl Interpreter {
         }
     }
 
-    pub fn solve_equation(&self, eq: &Node, guess: f64) -> InterpreterResult<f64> {
+    pub fn solve_equation(&self, eq: &Node, var: char, guess: f64) -> InterpreterResult<f64> {
         let f = self.move_equation(eq)?;
-        let derivative = self.visit(&self.differentiate(&f, 'x', None)?, None)?;
         let mut solution = guess;
         let mut error = None;
-        let mut step;
         let mut map = HashMap::new();
-        while error.is_none_or(|e| e > 0.0001) {
-            map.insert('x', solution);
-            step = self.visit(
-                &Node::Factors(vec![
-                    (TokenType::Mul, Node::Num(-1f64)),
-                    (TokenType::Mul, self.visit(&f, Some(&map))?),
-                    (TokenType::Div, self.visit(&derivative, Some(&map))?),
-                ]),
-                Some(&map),
-            )?;
-            if let Node::Num(h) = step {
-                solution += h;
-                error = Some(h);
-            } else {
+        let mut iterations = 0;
+        self.note_step(|| format!("Newton's method on {eq} from {var} = {guess}"));
+        while error.is_none_or(|e| e > 1e-12 * solution.abs().max(1f64)) {
+            if iterations == 100 {
+                return Err(InterpreterError::SolveError(String::from(
+                    "Did not converge",
+                )));
+            }
+            iterations += 1;
+            map.insert(var, solution);
+            let (value, slope) = self.derivative_at(&f, var, &map)?;
+            if slope == 0f64 {
+                return Err(InterpreterError::Undefined);
+            }
+            let h = -value / slope;
+            if !h.is_finite() {
                 return Err(InterpreterError::SolveError(String::from(
                     "Not substituted",
                 )));
             }
+            self.note_step(|| {
+                format!(
+                    "{iterations}: {var} = {solution}, residual = {value}, slope = {slope}, step = {h}"
+                )
+            });
+            solution += h;
+            error = Some(h.abs());
         }
+        self.note_step(|| format!("converged to {var} = {solution} in {iterations} iterations"));
 
         Ok(solution)
     }
 
+    pub fn evaluate(&self, node: &Node, map: &HashMap<char, f64>) -> InterpreterResult<f64> {
+        match self.visit(node, Some(map))? {
+            Node::Num(num) | Node::Estimate { value: num, .. } => Ok(num),
+            _ => Err(InterpreterError::SolveError(String::from(
+                "Not substituted",
+            ))),
+        }
+    }
+
+    pub fn evaluate_all(
+        &self,
+        nodes: &[Node],
+        map: &HashMap<char, f64>,
+    ) -> InterpreterResult<Vec<f64>> {
+        InterpreterResult::from_iter(nodes.iter().map(|node| self.evaluate(node, map)))
+    }
+
+    pub fn jacobian_at(
+        &self,
+        fs: &[Node],
+        vars: &[char],
+        map: &HashMap<char, f64>,
+    ) -> InterpreterResult<Vec<Vec<f64>>> {
+        InterpreterResult::from_iter(fs.iter().map(|f| Ok(self.gradient_at(f, vars, map)?.1)))
+    }
+
+    fn singular(vars: &[char], point: &[f64]) -> InterpreterError {
+        InterpreterError::SingularJacobian(
+            vars.iter().copied().zip(point.iter().copied()).collect(),
+        )
+    }
+
     pub fn solve_system(
         &self,
-        eq1: &Node,
-        eq2: &Node,
-        guess: (f64, f64),
-    ) -> InterpreterResult<(f64, f64)> {
-        let f1 = self.move_equation(eq1)?;
-        let f2 = self.move_equation(eq2)?;
-        let jacobian: ((Node, Node), (Node, Node)) = (
-            (
-                self.visit(&self.differentiate(&f1, 'x', None)?, None)?,
-                self.visit(&self.differentiate(&f1, 'y', None)?, None)?,
-            ),
-            (
-                self.visit(&self.differentiate(&f2, 'x', None)?, None)?,
-                self.visit(&self.differentiate(&f2, 'y', None)?, None)?,
-            ),
-        );
-        let det = self.visit(
-            &Node::Terms(vec![
-                (
-                    TokenType::Plus,
-                    Node::Factors(vec![
-                        (TokenType::Mul, jacobian.0 .0.clone()),
-                        (TokenType::Mul, jacobian.1 .1.clone()),
-                    ]),
-                ),
-                (
-                    TokenType::Minus,
-                    Node::Factors(vec![
-                        (TokenType::Mul, jacobian.0 .1.clone()),
-                        (TokenType::Mul, jacobian.1 .0.clone()),
-                    ]),
-                ),
-            ]),
-            None,
-        )?;
-        let inv = (
-            (
-                self.visit(
-                    &Node::Factors(vec![
-                        (TokenType::Mul, jacobian.1 .1),
-                        (TokenType::Div, det.clone()),
-                    ]),
-                    None,
-                )?,
-                self.visit(
-                    &Node::Factors(vec![
-                        (TokenType::Mul, Node::Num(-1f64)),
-                        (TokenType::Mul, jacobian.0 .1),
-                        (TokenType::Div, det.clone()),
-                    ]),
-                    None,
-                )?,
-            ),
-            (
-                self.visit(
-                    &Node::Factors(vec![
-                        (TokenType::Mul, Node::Num(-1f64)),
-                        (TokenType::Mul, jacobian.1 .0),
-                        (TokenType::Div, det.clone()),
-                    ]),
-                    None,
-                )?,
-                self.visit(
-                    &Node::Factors(vec![(TokenType::Mul, jacobian.0 .0), (TokenType::Div, det)]),
-                    None,
-                )?,
-            ),
-        );
-        let mut solution = guess;
-        let mut error = None;
-        let mut h;
+        eqs: &[Node],
+        vars: &[char],
+        guess: &[f64],
+        method: SystemMethod,
+    ) -> InterpreterResult<Vec<f64>> {
+        if eqs.len() != vars.len() || vars.len() != guess.len() {
+            return Err(InterpreterError::SolveError(format!(
+                "{} equations in {} unknowns",
+                eqs.len(),
+                vars.len()
+            )));
+        }
+        let fs =
+            InterpreterResult::<Vec<Node>>::from_iter(eqs.iter().map(|eq| self.move_equation(eq)))?;
+        let mut solution = guess.to_vec();
         let mut map = HashMap::new();
-        let mut val;
-        while error.is_none_or(|e| e > 0.00000001) {
-            map.insert('x', solution.0);
-            map.insert('y', solution.1);
-            val = (self.visit(&f1, Some(&map))?, self.visit(&f2, Some(&map))?);
-            h = (
-                self.visit(
-                    &Node::Terms(vec![
-                        (
-                            TokenType::Plus,
-                            Node::Factors(vec![
-                                (TokenType::Mul, Node::Num(-1f64)),
-                                (TokenType::Mul, inv.0 .0.clone()),
-                                (TokenType::Mul, val.0.clone()),
-                            ]),
-                        ),
-                        (
-                            TokenType::Plus,
-                            Node::Factors(vec![
-                                (TokenType::Mul, Node::Num(-1f64)),
-                                (TokenType::Mul, inv.0 .1.clone()),
-                                (TokenType::Mul, val.1.clone()),
-                            ]),
-                        ),
-                    ]),
-                    Some(&map),
-                )?,
-                self.visit(
-                    &Node::Terms(vec![
-                        (
-                            TokenType::Plus,
-                            Node::Factors(vec![
-                                (TokenType::Mul, Node::Num(-1f64)),
-                                (TokenType::Mul, inv.1 .0.clone()),
-                                (TokenType::Mul, val.0.clone()),
-                            ]),
-                        ),
-                        (
-                            TokenType::Plus,
-                            Node::Factors(vec![
-                                (TokenType::Mul, Node::Num(-1f64)),
-                                (TokenType::Mul, inv.1 .1.clone()),
-                                (TokenType::Mul, val.1.clone()),
-                            ]),
-                        ),
-                    ]),
-                    Some(&map),
-                )?,
-            );
-            if let Node::Num(hx) = h.0 {
-                if let Node::Num(hy) = h.1 {
-                    error = Some(hx.powi(2) + hy.powi(2));
-                    solution = (solution.0 + hx, solution.1 + hy);
-                } else {
-                    return Err(InterpreterError::SolveError(String::from(
-                        "Not substituted",
-                    )));
+        map.extend(vars.iter().copied().zip(solution.iter().copied()));
+        let mut val = self.evaluate_all(&fs, &map)?;
+        let mut matrix = self.jacobian_at(&fs, vars, &map)?;
+        let mut fresh = true;
+        for _ in 0..100 {
+            let Some(lu) = linalg::Lu::decompose(&matrix) else {
+                if fresh {
+                    return Err(Self::singular(vars, &solution));
                 }
-            } else {
-                return Err(InterpreterError::SolveError(String::from(
-                    "Not substituted",
-                )));
+                matrix = self.jacobian_at(&fs, vars, &map)?;
+                fresh = true;
+                continue;
+            };
+            let h = lu.solve(&val.iter().map(|v| -v).collect::<Vec<_>>());
+            let residual = linalg::norm(&val);
+            let mut damping = 1f64;
+            let (next, next_val) = loop {
+                let next = solution
+                    .iter()
+                    .zip(&h)
+                    .map(|(x, h)| x + damping * h)
+                    .collect::<Vec<_>>();
+                map.extend(vars.iter().copied().zip(next.iter().copied()));
+                match self.evaluate_all(&fs, &map) {
+                    Ok(next_val)
+                        if linalg::norm(&next_val) <= (1f64 - 1e-4 * damping) * residual
+                            || damping < 1e-4 =>
+                    {
+                        break (next, next_val);
+                    }
+                    Err(err) if damping < 1e-4 => return Err(err),
+                    _ => {}
+                }
+                damping /= 2f64;
+            };
+            if damping < 1e-4 && !fresh {
+                map.extend(vars.iter().copied().zip(solution.iter().copied()));
+                matrix = self.jacobian_at(&fs, vars, &map)?;
+                fresh = true;
+                continue;
             }
+            let dx = next
+                .iter()
+                .zip(&solution)
+                .map(|(a, b)| a - b)
+                .collect::<Vec<_>>();
+            let df = next_val
+                .iter()
+                .zip(&val)
+                .map(|(a, b)| a - b)
+                .collect::<Vec<_>>();
+            solution = next;
+            val = next_val;
+            if linalg::norm(&dx) < 1e-10 || linalg::norm(&val) < 1e-12 {
+                return Ok(solution);
+            }
+            match method {
+                SystemMethod::Newton => matrix = self.jacobian_at(&fs, vars, &map)?,
+                SystemMethod::Broyden => linalg::broyden_update(&mut matrix, &dx, &df),
+            }
+            fresh = method == SystemMethod::Newton;
         }
-        Ok(solution)
+        Err(InterpreterError::SolveError(String::from(
+            "Did not converge",
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SystemMethod;
+    use crate::interpreter::{parse, Interpreter, InterpreterError};
+
+    #[test]
+    fn solves_transcendental_equation() {
+        let x = Interpreter::new()
+            .solve_equation(&parse("cos(x)=x"), 'x', 0f64)
+            .unwrap();
+        assert!((x - 0.7390851332151607).abs() < 1e-12);
+    }
+
+    #[test]
+    fn solves_nonlinear_system_with_both_methods() {
+        let eqs = [parse("x^2+y^2=4"), parse("x*y=1")];
+        for method in [SystemMethod::Newton, SystemMethod::Broyden] {
+            let solution = Interpreter::new()
+                .solve_system(&eqs, &['x', 'y'], &[2f64, 0.5], method)
+                .unwrap();
+            assert!(
+                (solution[0] - 1.9318516525781366).abs() < 1e-8,
+                "{solution:?}"
+            );
+            assert!(
+                (solution[1] - 0.5176380902050415).abs() < 1e-8,
+                "{solution:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn reports_singular_jacobian() {
+        let eqs = [parse("x+y=1"), parse("2x+2y=3")];
+        let err = Interpreter::new()
+            .solve_system(&eqs, &['x', 'y'], &[0f64, 0f64], SystemMethod::Newton)
+            .unwrap_err();
+        assert!(matches!(err, InterpreterError::SingularJacobian(_)));
     }
 }
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use crate::node::Node;
+
+use super::{
+    differentiator::DifferentiatorError, Interpreter, InterpreterError, InterpreterResult,
+};
+
+const SAMPLES: usize = 5;
+const ATTEMPTS: usize = 50;
+const SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+struct Rng(u64);
+impl Rng {
+    fn uniform(&mut self, low: f64, high: f64) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        low + (high - low) * (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl Interpreter {
+    pub fn numeric_derivative(
+        &self,
+        f: &Node,
+        var: char,
+        at: &HashMap<char, f64>,
+    ) -> InterpreterResult<(f64, f64)> {
+        let x = at.get(&var).copied().unwrap_or(0f64);
+        let mut map = at.clone();
+        let mut value = |x: f64| {
+            map.insert(var, x);
+            self.evaluate(f, &map)
+        };
+        let mut h = 0.1 * x.abs().max(1f64);
+        let mut previous: Vec<f64> = vec![];
+        let (mut best, mut error) = (f64::NAN, f64::INFINITY);
+        for i in 0..12 {
+            let mut row = vec![(value(x + h)? - value(x - h)?) / (2f64 * h)];
+            if i == 0 {
+                best = row[0];
+            }
+            for j in 1..=i {
+                let factor = 4f64.powi(j as i32);
+                let extrapolated = row[j - 1] + (row[j - 1] - previous[j - 1]) / (factor - 1f64);
+                let estimate = (extrapolated - row[j - 1])
+                    .abs()
+                    .max((extrapolated - previous[j - 1]).abs());
+                row.push(extrapolated);
+                if estimate <= error {
+                    (best, error) = (extrapolated, estimate);
+                }
+            }
+            if i > 0 && (row[i] - previous[i - 1]).abs() >= 2f64 * error {
+                break;
+            }
+            previous = row;
+            h /= 2f64;
+        }
+        Ok((best, error))
+    }
+
+    pub fn check_derivative(
+        &self,
+        f: &Node,
+        var: char,
+        derivative: &Node,
+    ) -> InterpreterResult<()> {
+        let mut vars = self.unknowns(f);
+        vars.extend(self.unknowns(derivative));
+        vars.push(var);
+        vars.sort_unstable();
+        vars.dedup();
+        let mut rng = Rng(SEED);
+        let mut checked = 0;
+        for _ in 0..ATTEMPTS {
+            if checked == SAMPLES {
+                break;
+            }
+            let point = vars
+                .iter()
+                .map(|var| (*var, rng.uniform(-3f64, 3f64)))
+                .collect::<HashMap<_, _>>();
+            let Ok(symbolic) = self.evaluate(derivative, &point) else {
+                continue;
+            };
+            let Ok((numeric, error)) = self.numeric_derivative(f, var, &point) else {
+                continue;
+            };
+            if !symbolic.is_finite() || !numeric.is_finite() {
+                continue;
+            }
+            checked += 1;
+            if (symbolic - numeric).abs() > 1e-6 * symbolic.abs().max(1f64) + 10f64 * error {
+                let mut point = point.into_iter().collect::<Vec<_>>();
+                point.sort_by_key(|(var, _)| *var);
+                return Err(InterpreterError::DifferentiatorError(
+                    DifferentiatorError::Mismatch {
+                        derivative: derivative.clone(),
+                        point,
+                        symbolic,
+                        numeric,
+                    },
+                ));
+            }
+        }
+        if checked < SAMPLES {
+            return Err(InterpreterError::DifferentiatorError(
+                DifferentiatorError::Unverified {
+                    derivative: derivative.clone(),
+                    checked,
+                },
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::interpreter::{
+        differentiator::DifferentiatorError, parse, Interpreter, InterpreterError,
+    };
+
+    #[test]
+    fn richardson_central_differences() {
+        let interpreter = Interpreter::new();
+        for (f, x, expected) in [
+            ("sin(x)", 1f64, 1f64.cos()),
+            ("x^x", 2f64, 4f64 * (2f64.ln() + 1f64)),
+            ("e^(3x)", 0.5, 3f64 * 1.5f64.exp()),
+        ] {
+            let (slope, error) = interpreter
+                .numeric_derivative(&parse(f), 'x', &HashMap::from([('x', x)]))
+                .unwrap();
+            assert!(
+                (slope - expected).abs() < 1e-8 * expected.abs(),
+                "{f}: {slope}"
+            );
+            assert!(error < 1e-6, "{f}: {error}");
+        }
+    }
+
+    #[test]
+    fn cross_check_accepts_correct_derivative() {
+        let interpreter = Interpreter::new();
+        assert!(interpreter
+            .check_derivative(&parse("x^2*sin(x)"), 'x', &parse("2x*sin(x)+x^2*cos(x)"))
+            .is_ok());
+    }
+
+    #[test]
+    fn cross_check_rejects_wrong_derivative() {
+        let interpreter = Interpreter::new();
+        assert!(matches!(
+            interpreter.check_derivative(&parse("x^2*sin(x)"), 'x', &parse("2x*cos(x)")),
+            Err(InterpreterError::DifferentiatorError(
+                DifferentiatorError::Mismatch { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn cross_check_needs_defined_sample_points() {
+        let interpreter = Interpreter::new();
+        assert!(matches!(
+            interpreter.check_derivative(&parse("sqrt(x-5)"), 'x', &parse("1/(2sqrt(x-5))")),
+            Err(InterpreterError::DifferentiatorError(
+                DifferentiatorError::Unverified { checked: 0, .. }
+            ))
+        ));
+    }
+}
@@ -0,0 +1,538 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use crate::{node::Node, token::TokenType};
+
+use super::{Interpreter, InterpreterError, InterpreterResult};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OdeMethod {
+    Rk4,
+    DormandPrince,
+}
+
+const RK4_STEP: f64 = 1e-3;
+const RK4_MAX_STEPS: f64 = 1e7;
+
+const DP_A: [[f64; 6]; 7] = [
+    [0f64, 0f64, 0f64, 0f64, 0f64, 0f64],
+    [1f64 / 5f64, 0f64, 0f64, 0f64, 0f64, 0f64],
+    [3f64 / 40f64, 9f64 / 40f64, 0f64, 0f64, 0f64, 0f64],
+    [
+        44f64 / 45f64,
+        -56f64 / 15f64,
+        32f64 / 9f64,
+        0f64,
+        0f64,
+        0f64,
+    ],
+    [
+        19372f64 / 6561f64,
+        -25360f64 / 2187f64,
+        64448f64 / 6561f64,
+        -212f64 / 729f64,
+        0f64,
+        0f64,
+    ],
+    [
+        9017f64 / 3168f64,
+        -355f64 / 33f64,
+        46732f64 / 5247f64,
+        49f64 / 176f64,
+        -5103f64 / 18656f64,
+        0f64,
+    ],
+    [
+        35f64 / 384f64,
+        0f64,
+        500f64 / 1113f64,
+        125f64 / 192f64,
+        -2187f64 / 6784f64,
+        11f64 / 84f64,
+    ],
+];
+const DP_C: [f64; 7] = [0f64, 0.2, 0.3, 0.8, 8f64 / 9f64, 1f64, 1f64];
+const DP_B: [f64; 7] = [
+    35f64 / 384f64,
+    0f64,
+    500f64 / 1113f64,
+    125f64 / 192f64,
+    -2187f64 / 6784f64,
+    11f64 / 84f64,
+    0f64,
+];
+const DP_B_LOW: [f64; 7] = [
+    5179f64 / 57600f64,
+    0f64,
+    7571f64 / 16695f64,
+    393f64 / 640f64,
+    -92097f64 / 339200f64,
+    187f64 / 2100f64,
+    1f64 / 40f64,
+];
+
+struct Ode {
+    var: char,
+    unknowns: Vec<char>,
+    rates: Vec<Node>,
+    start: f64,
+    initial: Vec<f64>,
+}
+
+enum OdeTarget {
+    Point(f64),
+    Range(f64, f64),
+}
+
+pub fn unwrap(node: &Node) -> &Node {
+    match node {
+        Node::Terms(nodes) => match &nodes[..] {
+            [(TokenType::Plus, node)] => unwrap(node),
+            _ => node,
+        },
+        Node::Factors(nodes) => match &nodes[..] {
+            [(TokenType::Mul, node)] => unwrap(node),
+            _ => node,
+        },
+        _ => node,
+    }
+}
+
+pub fn derivative_order(node: &Node) -> Option<(char, char, u32)> {
+    let Node::Derivative { derivative, vars } = unwrap(node) else {
+        return None;
+    };
+    let [(var, order)] = vars[..] else {
+        return None;
+    };
+    match derivative.as_var() {
+        Some(dependent) => (dependent != var).then_some((dependent, var, order)),
+        None => {
+            let (dependent, inner, inner_order) = derivative_order(derivative)?;
+            (inner == var).then_some((dependent, inner, inner_order + order))
+        }
+    }
+}
+
+pub fn derivatives(node: &Node) -> Vec<(char, char, u32)> {
+    let found = RefCell::new(vec![]);
+    node.replace(&|node| {
+        let derivative = derivative_order(node)?;
+        found.borrow_mut().push(derivative);
+        Some(node.clone())
+    });
+    found.into_inner()
+}
+
+fn primes(node: &Node) -> Vec<(char, Option<char>)> {
+    let found = RefCell::new(vec![]);
+    node.replace(&|node| match node {
+        Node::Prime { var, wrt, .. } => {
+            found.borrow_mut().push((*var, *wrt));
+            Some(node.clone())
+        }
+        _ => None,
+    });
+    found.into_inner()
+}
+
+fn derivative_of(node: &Node) -> Option<(char, char)> {
+    match derivative_order(node)? {
+        (dependent, var, 1) => Some((dependent, var)),
+        _ => None,
+    }
+}
+
+fn factors(node: &Node) -> Option<&[(TokenType, Node)]> {
+    match node {
+        Node::Factors(inner) => match &inner[..] {
+            [(TokenType::Mul, node)] => factors(node),
+            _ => Some(inner),
+        },
+        Node::Terms(terms) => match &terms[..] {
+            [(TokenType::Plus, node)] => factors(node),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn axpy(y: &[f64], h: f64, k: &[f64]) -> Vec<f64> {
+    y.iter().zip(k).map(|(y, k)| y + h * k).collect()
+}
+
+impl Interpreter {
+    pub fn is_ode(&self, statements: &[Node]) -> bool {
+        statements.iter().any(|statement| {
+            matches!(statement, Node::Equation { .. })
+                && !(derivatives(statement).is_empty() && primes(statement).is_empty())
+        })
+    }
+
+    fn resolve_primes(&self, statements: &[Node]) -> InterpreterResult<Vec<Node>> {
+        let primed = statements.iter().flat_map(primes).collect::<Vec<_>>();
+        let Some((first, _)) = primed.first() else {
+            return Ok(statements.to_vec());
+        };
+        let mut dependent = primed.iter().map(|(var, _)| *var).collect::<Vec<_>>();
+        dependent.extend(
+            statements
+                .iter()
+                .flat_map(derivatives)
+                .map(|(dependent, _, _)| dependent),
+        );
+        let free = |node: &Node| {
+            let mut vars = self.unknowns(node);
+            vars.retain(|var| !dependent.contains(var));
+            vars
+        };
+        let explicit = primed.iter().filter_map(|(_, wrt)| *wrt).chain(
+            statements
+                .iter()
+                .flat_map(derivatives)
+                .map(|(_, var, _)| var),
+        );
+        let targets = statements.iter().filter_map(|statement| match statement {
+            Node::Equation { lhs, .. } => lhs.as_var().filter(|var| !dependent.contains(var)),
+            _ => None,
+        });
+        let mut candidates = statements.iter().flat_map(free).collect::<Vec<_>>();
+        candidates.sort_unstable();
+        candidates.dedup();
+        let var = match explicit.chain(targets).next() {
+            Some(var) => var,
+            None => match candidates[..] {
+                [var] => var,
+                _ => {
+                    return Err(InterpreterError::SolveError(format!(
+                        "Cannot determine the independent variable, write {first}'(x)"
+                    )))
+                }
+            },
+        };
+        Ok(statements
+            .iter()
+            .map(|statement| {
+                statement.replace(&|node| match node {
+                    Node::Prime {
+                        var: dependent,
+                        order,
+                        wrt,
+                    } => Some(Node::Derivative {
+                        derivative: Box::new(Node::Var(*dependent)),
+                        vars: vec![(wrt.unwrap_or(var), *order)],
+                    }),
+                    _ => None,
+                })
+            })
+            .collect())
+    }
+
+    pub fn condition(&self, lhs: &Node, rhs: &Node) -> Option<(char, u32, f64, f64)> {
+        let [(TokenType::Mul, unknown), (TokenType::Mul, at)] = factors(lhs)? else {
+            return None;
+        };
+        let (unknown, order) = match unknown.as_var() {
+            Some(unknown) => (unknown, 0),
+            None => {
+                let (unknown, _, order) = derivative_order(unknown)?;
+                (unknown, order)
+            }
+        };
+        let origin = HashMap::new();
+        Some((
+            unknown,
+            order,
+            self.evaluate(at, &origin).ok()?,
+            self.evaluate(rhs, &origin).ok()?,
+        ))
+    }
+
+    fn target(&self, statements: &[Node]) -> Option<char> {
+        let (_, var, _) = statements.iter().flat_map(derivatives).next()?;
+        statements
+            .iter()
+            .any(|statement| {
+                matches!(statement, Node::Equation { lhs, .. } if lhs.as_var() == Some(var))
+            })
+            .then_some(var)
+    }
+
+    fn ode(&self, statements: &[Node]) -> InterpreterResult<(Ode, Option<OdeTarget>)> {
+        let error = |s: &str| InterpreterError::SolveError(String::from(s));
+        let mut var = None;
+        let mut unknowns = vec![];
+        let mut rates = vec![];
+        let mut rest = vec![];
+        for statement in statements {
+            match statement {
+                Node::Equation { lhs, rhs } => match derivative_of(lhs) {
+                    Some((unknown, v)) => {
+                        if var.is_some_and(|var| var != v) {
+                            return Err(error("Mixed independent variables"));
+                        }
+                        var = Some(v);
+                        unknowns.push(unknown);
+                        rates.push(*rhs.clone());
+                    }
+                    None => rest.push((lhs, rhs)),
+                },
+                _ => return Err(error("Expected an equation")),
+            }
+        }
+        let var = var.ok_or_else(|| error("Not a differential equation"))?;
+        let origin = HashMap::new();
+        let mut start = None;
+        let mut initial = vec![None; unknowns.len()];
+        let mut target = None;
+        for (lhs, rhs) in rest {
+            if lhs.as_var() == Some(var) {
+                target = Some(match rhs.as_ref() {
+                    Node::Range { start, end } => OdeTarget::Range(
+                        self.evaluate(start, &origin)?,
+                        self.evaluate(end, &origin)?,
+                    ),
+                    rhs => OdeTarget::Point(self.evaluate(rhs, &origin)?),
+                });
+                continue;
+            }
+            let condition = self
+                .condition(lhs, rhs)
+                .and_then(|(unknown, order, at, value)| {
+                    let i = unknowns.iter().position(|v| *v == unknown)?;
+                    (order == 0).then_some((i, at, value))
+                });
+            let Some((i, at, value)) = condition else {
+                return Err(InterpreterError::SolveError(format!(
+                    "Unrecognized condition: {lhs} = {rhs}"
+                )));
+            };
+            if start.is_some_and(|start| start != at) {
+                return Err(error("Initial conditions must share a starting point"));
+            }
+            start = Some(at);
+            initial[i] = Some(value);
+        }
+        let initial = initial
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| error("Missing initial condition"))?;
+        Ok((
+            Ode {
+                var,
+                unknowns,
+                rates,
+                start: start.ok_or_else(|| error("Missing initial condition"))?,
+                initial,
+            },
+            target,
+        ))
+    }
+
+    fn rates_at(&self, ode: &Ode, x: f64, y: &[f64]) -> InterpreterResult<Vec<f64>> {
+        let mut map = HashMap::from([(ode.var, x)]);
+        map.extend(ode.unknowns.iter().copied().zip(y.iter().copied()));
+        self.evaluate_all(&ode.rates, &map)
+    }
+
+    fn rk4(&self, ode: &Ode, x0: f64, y0: &[f64], x1: f64) -> InterpreterResult<Vec<f64>> {
+        let steps = ((x1 - x0).abs() / RK4_STEP).ceil().max(1f64);
+        if steps > RK4_MAX_STEPS {
+            return Err(InterpreterError::SolveError(String::from(
+                "Interval too long for RK4, use :ode dopri",
+            )));
+        }
+        let steps = steps as usize;
+        let h = (x1 - x0) / steps as f64;
+        let mut y = y0.to_vec();
+        for i in 0..steps {
+            let x = x0 + h * i as f64;
+            let k1 = self.rates_at(ode, x, &y)?;
+            let k2 = self.rates_at(ode, x + h / 2f64, &axpy(&y, h / 2f64, &k1))?;
+            let k3 = self.rates_at(ode, x + h / 2f64, &axpy(&y, h / 2f64, &k2))?;
+            let k4 = self.rates_at(ode, x + h, &axpy(&y, h, &k3))?;
+            for (j, y) in y.iter_mut().enumerate() {
+                *y += h / 6f64 * (k1[j] + 2f64 * k2[j] + 2f64 * k3[j] + k4[j]);
+            }
+        }
+        Ok(y)
+    }
+
+    fn dormand_prince(
+        &self,
+        ode: &Ode,
+        x0: f64,
+        y0: &[f64],
+        x1: f64,
+    ) -> InterpreterResult<Vec<f64>> {
+        let (rtol, atol) = (1e-10, 1e-12);
+        let direction = (x1 - x0).signum();
+        let mut h = (x1 - x0) / 100f64;
+        let mut x = x0;
+        let mut y = y0.to_vec();
+        let mut steps = 0;
+        while (x1 - x) * direction > 0f64 {
+            if (x + h - x1) * direction > 0f64 {
+                h = x1 - x;
+            }
+            let mut k: Vec<Vec<f64>> = Vec::with_capacity(7);
+            for stage in 0..7 {
+                let mut yi = y.clone();
+                for (j, kj) in k.iter().enumerate() {
+                    for (yi, kj) in yi.iter_mut().zip(kj) {
+                        *yi += h * DP_A[stage][j] * kj;
+                    }
+                }
+                k.push(self.rates_at(ode, x + DP_C[stage] * h, &yi)?);
+            }
+            let next = (0..y.len())
+                .map(|i| y[i] + h * (0..7).map(|s| DP_B[s] * k[s][i]).sum::<f64>())
+                .collect::<Vec<_>>();
+            let error = (0..y.len())
+                .map(|i| {
+                    let e = h
+                        * (0..7)
+                            .map(|s| (DP_B[s] - DP_B_LOW[s]) * k[s][i])
+                            .sum::<f64>();
+                    e.abs() / (atol + rtol * y[i].abs().max(next[i].abs()))
+                })
+                .fold(0f64, f64::max);
+            if error <= 1f64 {
+                x += h;
+                y = next;
+            }
+            h *= (0.9 * error.powf(-0.2)).clamp(0.2, 5f64);
+            steps += 1;
+            if steps > 100_000 || !h.is_finite() || h.abs() < 1e-14 * x.abs().max(1f64) {
+                return Err(InterpreterError::SolveError(format!(
+                    "Step size underflow at {} = {x}",
+                    ode.var
+                )));
+            }
+        }
+        Ok(y)
+    }
+
+    fn propagate(&self, ode: &Ode, x0: f64, y0: &[f64], x1: f64) -> InterpreterResult<Vec<f64>> {
+        if x0 == x1 {
+            return Ok(y0.to_vec());
+        }
+        match self.ode_method {
+            OdeMethod::Rk4 => self.rk4(ode, x0, y0, x1),
+            OdeMethod::DormandPrince => self.dormand_prince(ode, x0, y0, x1),
+        }
+    }
+
+    pub fn solve_ode(&self, statements: &[Node]) -> InterpreterResult<Node> {
+        let statements = &self.resolve_primes(statements)?;
+        if self.target(statements).is_none() {
+            return self.dsolve(statements);
+        }
+        let (ode, target) = self.ode(statements)?;
+        match target {
+            Some(OdeTarget::Point(x)) => {
+                let y = self.propagate(&ode, ode.start, &ode.initial, x)?;
+                Ok(Node::System(
+                    ode.unknowns
+                        .iter()
+                        .zip(y)
+                        .map(|(unknown, value)| Node::Equation {
+                            lhs: Box::new(Node::Var(*unknown)),
+                            rhs: Box::new(Node::Num(value)),
+                        })
+                        .collect(),
+                ))
+            }
+            Some(OdeTarget::Range(a, b)) => {
+                let steps = 10;
+                let mut x = ode.start;
+                let mut y = ode.initial.clone();
+                let mut rows = vec![];
+                for i in 0..=steps {
+                    let next = a + (b - a) * i as f64 / steps as f64;
+                    y = self.propagate(&ode, x, &y, next)?;
+                    x = next;
+                    rows.push([x].into_iter().chain(y.iter().copied()).collect());
+                }
+                let mut headers = vec![ode.var];
+                headers.extend(&ode.unknowns);
+                Ok(Node::Table { headers, rows })
+            }
+            None => Err(InterpreterError::SolveError(format!(
+                "Specify {0} = value or {0} = a..b",
+                ode.var
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OdeMethod;
+    use crate::{
+        interpreter::{parse, run, Interpreter, InterpreterError},
+        node::Node,
+    };
+
+    fn solve_at(method: OdeMethod, statements: &[&str]) -> f64 {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_ode_method(method);
+        let statements = statements.iter().map(|s| parse(s)).collect::<Vec<_>>();
+        match interpreter.solve_ode(&statements) {
+            Ok(Node::System(equations)) => match &equations[..] {
+                [Node::Equation { rhs, .. }] => match **rhs {
+                    Node::Num(y) => y,
+                    ref other => panic!("{other:?}"),
+                },
+                other => panic!("{other:?}"),
+            },
+            other => panic!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn exponential_growth_over_long_interval() {
+        for method in [OdeMethod::Rk4, OdeMethod::DormandPrince] {
+            let y = solve_at(method, &["dy/dx = y", "y(0) = 1", "x = 20"]);
+            assert!((y / 20f64.exp() - 1f64).abs() < 1e-8, "{method:?}: {y}");
+        }
+    }
+
+    #[test]
+    fn logistic_equation() {
+        for method in [OdeMethod::Rk4, OdeMethod::DormandPrince] {
+            let y = solve_at(method, &["dy/dx = y*(1-y)", "y(0) = 0.5", "x = 2"]);
+            let expected = 1f64 / (1f64 + (-2f64).exp());
+            assert!((y - expected).abs() < 1e-9, "{method:?}: {y}");
+        }
+    }
+
+    #[test]
+    fn prime_takes_variable_from_target() {
+        let y = solve_at(OdeMethod::DormandPrince, &["y' = t*y", "y(0) = 1", "t = 2"]);
+        assert!((y - 2f64.exp()).abs() < 1e-8, "{y}");
+    }
+
+    #[test]
+    fn prime_of_an_undefined_function_is_an_error() {
+        for line in ["f''(x)", "f'(t) + 1", "d/dx(f'(x))"] {
+            assert!(
+                matches!(run(line), Err(InterpreterError::SolveError(_))),
+                "{line}"
+            );
+        }
+        assert_eq!(
+            run("y''(x) + y = 0").unwrap().to_string(),
+            "y = C*cos(x)+D*sin(x)"
+        );
+    }
+
+    #[test]
+    fn prime_without_variable_is_an_error() {
+        for line in ["f'(2)", "y''", "y'' + y = 0"] {
+            assert!(
+                matches!(run(line), Err(InterpreterError::SolveError(_))),
+                "{line}"
+            );
+        }
+    }
+}
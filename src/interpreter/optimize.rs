@@ -0,0 +1,393 @@
+use std::collections::HashMap;
+
+use crate::{
+    node::Node,
+    token::{CommandType, TokenType},
+};
+
+use super::{linalg, Interpreter, InterpreterError, InterpreterResult};
+
+const GOLDEN: f64 = 0.381_966_011_250_105_1;
+
+impl Interpreter {
+    fn value_at(&self, f: &Node, var: char, x: f64) -> f64 {
+        self.evaluate(f, &HashMap::from([(var, x)]))
+            .ok()
+            .filter(|v| !v.is_nan())
+            .unwrap_or(f64::INFINITY)
+    }
+
+    fn brent(&self, f: &Node, var: char, mut a: f64, mut b: f64) -> (f64, f64) {
+        let mut x = a + GOLDEN * (b - a);
+        let (mut w, mut v) = (x, x);
+        let mut fx = self.value_at(f, var, x);
+        let (mut fw, mut fv) = (fx, fx);
+        let (mut d, mut e) = (0f64, 0f64);
+        for _ in 0..200 {
+            let mid = (a + b) / 2f64;
+            let tol = 1e-10 * x.abs() + 1e-12;
+            if (x - mid).abs() <= 2f64 * tol - (b - a) / 2f64 {
+                break;
+            }
+            let mut golden = true;
+            if e.abs() > tol {
+                let r = (x - w) * (fx - fv);
+                let mut q = (x - v) * (fx - fw);
+                let mut p = (x - v) * q - (x - w) * r;
+                q = 2f64 * (q - r);
+                if q > 0f64 {
+                    p = -p;
+                }
+                q = q.abs();
+                if p.abs() < (q * e / 2f64).abs() && p > q * (a - x) && p < q * (b - x) {
+                    e = d;
+                    d = p / q;
+                    golden = false;
+                }
+            }
+            if golden {
+                e = if x >= mid { a - x } else { b - x };
+                d = GOLDEN * e;
+            }
+            let u = x + if d.abs() >= tol { d } else { tol.copysign(d) };
+            let fu = self.value_at(f, var, u);
+            if fu <= fx {
+                if u >= x {
+                    a = x;
+                } else {
+                    b = x;
+                }
+                (v, fv, w, fw, x, fx) = (w, fw, x, fx, u, fu);
+            } else {
+                if u < x {
+                    a = u;
+                } else {
+                    b = u;
+                }
+                if fu <= fw || w == x {
+                    (v, fv, w, fw) = (w, fw, u, fu);
+                } else if fu <= fv || v == x || v == w {
+                    (v, fv) = (u, fu);
+                }
+            }
+        }
+        (x, fx)
+    }
+
+    fn stationary_points(&self, f: &Node, var: char) -> InterpreterResult<Vec<(Node, f64)>> {
+        let derivative = self.visit(&self.differentiate(f, var, None)?, None)?;
+        let eq = Node::Equation {
+            lhs: Box::new(derivative),
+            rhs: Box::new(Node::Num(0f64)),
+        };
+        if let Ok(points) = self.solve_for(&eq, var) {
+            return points
+                .into_iter()
+                .map(|point| {
+                    let x = self.evaluate(&point, &HashMap::new())?;
+                    Ok((point, x))
+                })
+                .collect();
+        }
+        let mut points: Vec<(Node, f64)> = vec![];
+        for guess in [1f64, -1f64, 0.1, -0.1, 10f64, -10f64] {
+            if let Ok(x) = self.solve_equation(&eq, var, guess) {
+                if points
+                    .iter()
+                    .all(|(_, p)| (p - x).abs() > 1e-9 * x.abs().max(1f64))
+                {
+                    points.push((Node::Num(x), x));
+                }
+            }
+        }
+        Ok(points)
+    }
+
+    fn is_local_min(&self, f: &Node, var: char, x: f64) -> InterpreterResult<bool> {
+        let second = self.visit(
+            &self.differentiate(&self.differentiate(f, var, None)?, var, None)?,
+            None,
+        )?;
+        match self.evaluate(&second, &HashMap::from([(var, x)])) {
+            Ok(curvature) if curvature.abs() > 1e-12 => Ok(curvature > 0f64),
+            _ => {
+                let h = 1e-4 * x.abs().max(1f64);
+                let fx = self.value_at(f, var, x);
+                Ok(self.value_at(f, var, x - h) >= fx && self.value_at(f, var, x + h) >= fx)
+            }
+        }
+    }
+
+    fn minimize_scalar(
+        &self,
+        f: &Node,
+        var: char,
+        range: Option<(f64, f64)>,
+    ) -> InterpreterResult<(Node, f64)> {
+        let derivative = self.visit(&self.differentiate(f, var, None)?, None)?;
+        let Some((a, b)) = range else {
+            let mut best: Option<(Node, f64)> = None;
+            for (point, x) in self.stationary_points(f, var)? {
+                let value = self.value_at(f, var, x);
+                if self.is_local_min(f, var, x)? && best.as_ref().is_none_or(|b| value < b.1) {
+                    best = Some((point, value));
+                }
+            }
+            return best
+                .ok_or_else(|| InterpreterError::SolveError(String::from("No minimum found")));
+        };
+        let (a, b) = (a.min(b), a.max(b));
+        let mut candidates = vec![(Node::Num(a), a), (Node::Num(b), b)];
+        if self.polynomial(&derivative, var).is_some() {
+            candidates.extend(
+                self.stationary_points(f, var)?
+                    .into_iter()
+                    .filter(|(_, x)| a <= *x && *x <= b),
+            );
+        } else {
+            let samples = 200;
+            let grid = (0..=samples)
+                .map(|i| a + (b - a) * i as f64 / samples as f64)
+                .collect::<Vec<_>>();
+            let best = (0..=samples)
+                .min_by(|i, j| {
+                    self.value_at(f, var, grid[*i])
+                        .total_cmp(&self.value_at(f, var, grid[*j]))
+                })
+                .expect("grid is not empty");
+            let (x, _) = self.brent(
+                f,
+                var,
+                grid[best.saturating_sub(1)],
+                grid[(best + 1).min(samples)],
+            );
+            candidates.push((Node::Num(x), x));
+        }
+        candidates
+            .into_iter()
+            .map(|(point, x)| {
+                let value = self.value_at(f, var, x);
+                (point, value)
+            })
+            .filter(|(_, value)| value.is_finite())
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .ok_or_else(|| InterpreterError::SolveError(String::from("No minimum found")))
+    }
+
+    fn bfgs(&self, f: &Node, vars: &[char], guess: &[f64]) -> InterpreterResult<(Vec<f64>, f64)> {
+        let n = vars.len();
+        let at = |x: &[f64]| {
+            vars.iter()
+                .copied()
+                .zip(x.iter().copied())
+                .collect::<HashMap<_, _>>()
+        };
+        let value = |x: &[f64]| {
+            self.evaluate(f, &at(x))
+                .ok()
+                .filter(|v| !v.is_nan())
+                .unwrap_or(f64::INFINITY)
+        };
+        let mut x = guess.to_vec();
+        let mut fx = self.evaluate(f, &at(&x))?;
+        let mut g = self.gradient_at(f, vars, &at(&x))?.1;
+        let identity = (0..n)
+            .map(|i| (0..n).map(|j| if i == j { 1f64 } else { 0f64 }).collect())
+            .collect::<Vec<Vec<_>>>();
+        let mut h = identity.clone();
+        for _ in 0..500 {
+            if linalg::norm(&g) < 1e-9 {
+                break;
+            }
+            let mut p = h
+                .iter()
+                .map(|row| -linalg::dot(row, &g))
+                .collect::<Vec<_>>();
+            if linalg::dot(&p, &g) >= 0f64 {
+                h = identity.clone();
+                p = g.iter().map(|v| -v).collect();
+            }
+            let slope = linalg::dot(&p, &g);
+            let mut t = 1f64;
+            let (next, f_next) = loop {
+                let next = x.iter().zip(&p).map(|(x, p)| x + t * p).collect::<Vec<_>>();
+                let f_next = value(&next);
+                if f_next <= fx + 1e-4 * t * slope || t < 1e-12 {
+                    break (next, f_next);
+                }
+                t /= 2f64;
+            };
+            if t < 1e-12 {
+                break;
+            }
+            if f_next < -1e100 {
+                return Err(InterpreterError::SolveError(String::from("Unbounded")));
+            }
+            let g_next = self.gradient_at(f, vars, &at(&next))?.1;
+            let s = next.iter().zip(&x).map(|(a, b)| a - b).collect::<Vec<_>>();
+            let y = g_next
+                .iter()
+                .zip(&g)
+                .map(|(a, b)| a - b)
+                .collect::<Vec<_>>();
+            let sy = linalg::dot(&s, &y);
+            if sy > 1e-12 {
+                let hy = h.iter().map(|row| linalg::dot(row, &y)).collect::<Vec<_>>();
+                let yhy = linalg::dot(&y, &hy);
+                for i in 0..n {
+                    for j in 0..n {
+                        h[i][j] += ((sy + yhy) * s[i] * s[j]) / (sy * sy)
+                            - (hy[i] * s[j] + s[i] * hy[j]) / sy;
+                    }
+                }
+            }
+            (x, fx, g) = (next, f_next, g_next);
+        }
+        Ok((x, fx))
+    }
+
+    pub fn optimize(&self, args: &[Node], maximize: bool) -> InterpreterResult<Node> {
+        let Some((expr, rest)) = args.split_first() else {
+            return Err(InterpreterError::SolveError(String::from(
+                "Nothing to optimize",
+            )));
+        };
+        let sign = if maximize { -1f64 } else { 1f64 };
+        let f = Node::Factors(vec![
+            (TokenType::Mul, Node::Num(sign)),
+            (TokenType::Mul, expr.clone()),
+        ]);
+        let unknowns = self.unknowns(expr);
+        let equation = |var: char, value: Node| Node::Equation {
+            lhs: Box::new(Node::Var(var)),
+            rhs: Box::new(value),
+        };
+        let visited = self.visit(expr, None)?;
+        let result = |value: f64| Node::Equation {
+            lhs: Box::new(visited.clone()),
+            rhs: Box::new(Node::Num(sign * value)),
+        };
+        let guesses = rest
+            .iter()
+            .map(|arg| match arg {
+                Node::Equation { lhs, rhs } => {
+                    Some((lhs.as_var()?, self.evaluate(rhs, &HashMap::new()).ok()?))
+                }
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>();
+        let scalar = match rest {
+            [] => match unknowns[..] {
+                [var] => Some((var, None)),
+                _ => None,
+            },
+            [var] => var.as_var().map(|var| (var, None)),
+            [var, Node::Range { start, end }] => Some((
+                var.as_var().ok_or_else(|| {
+                    InterpreterError::SolveError(String::from("Expected a variable"))
+                })?,
+                Some((
+                    self.evaluate(start, &HashMap::new())?,
+                    self.evaluate(end, &HashMap::new())?,
+                )),
+            )),
+            _ => None,
+        };
+        if let Some((var, range)) = scalar {
+            if unknowns.iter().any(|unknown| *unknown != var) {
+                return Err(InterpreterError::InvalidArguments(if maximize {
+                    CommandType::Maximize
+                } else {
+                    CommandType::Minimize
+                }));
+            }
+            let (point, value) = self.minimize_scalar(&f, var, range)?;
+            return Ok(Node::System(vec![equation(var, point), result(value)]));
+        }
+        let (vars, guess): (Vec<char>, Vec<f64>) = match guesses {
+            Some(guesses) if !guesses.is_empty() => guesses.into_iter().unzip(),
+            _ if rest.is_empty() => (unknowns.clone(), vec![0f64; unknowns.len()]),
+            _ => {
+                return Err(InterpreterError::SolveError(String::from(
+                    "Expected a variable, a range or starting values",
+                )))
+            }
+        };
+        let (point, value) = self.bfgs(&f, &vars, &guess)?;
+        let mut nodes = vars
+            .into_iter()
+            .zip(point)
+            .map(|(var, x)| equation(var, Node::Num(x)))
+            .collect::<Vec<_>>();
+        nodes.push(result(value));
+        Ok(Node::System(nodes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interpreter::{run, InterpreterError},
+        node::Node,
+    };
+
+    fn optimum(line: &str) -> Vec<f64> {
+        let Ok(Node::System(equations)) = run(line) else {
+            panic!("{line}")
+        };
+        equations
+            .iter()
+            .map(|equation| match equation {
+                Node::Equation { rhs, .. } => match **rhs {
+                    Node::Num(value) | Node::Estimate { value, .. } => value,
+                    ref other => panic!("{other}"),
+                },
+                other => panic!("{other}"),
+            })
+            .collect()
+    }
+
+    fn assert_optimum(line: &str, expected: &[f64]) {
+        let found = optimum(line);
+        assert_eq!(found.len(), expected.len(), "{line}");
+        for (found, expected) in found.iter().zip(expected) {
+            assert!((found - expected).abs() < 1e-6, "{line}: {found}");
+        }
+    }
+
+    #[test]
+    fn quadratic_minimum() {
+        assert_optimum("minimize(x^2-4x+1, x)", &[2f64, -3f64]);
+    }
+
+    #[test]
+    fn maximum_on_an_interval() {
+        assert_optimum(
+            "maximize(sin(x), x, 0..3)",
+            &[std::f64::consts::FRAC_PI_2, 1f64],
+        );
+    }
+
+    #[test]
+    fn several_variables() {
+        assert_optimum("minimize((x-1)^2+(y+2)^2)", &[1f64, -2f64, 0f64]);
+    }
+
+    #[test]
+    fn other_unknowns_are_invalid_for_a_single_variable() {
+        for line in ["minimize(x^2+y^2, x)", "maximize(x*y, y, 0..1)"] {
+            assert!(
+                matches!(run(line), Err(InterpreterError::InvalidArguments(_))),
+                "{line}"
+            );
+        }
+    }
+
+    #[test]
+    fn local_minimum_from_a_starting_value() {
+        let [x, _] = optimum("minimize(x^4-3x^2+x, x=1)")[..] else {
+            panic!()
+        };
+        assert!((4f64 * x.powi(3) - 6f64 * x + 1f64).abs() < 1e-6);
+    }
+}
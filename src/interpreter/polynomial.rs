@@ -0,0 +1,201 @@
+use std::ops::{Add, Mul, Neg, Sub};
+
+use crate::{node::Node, token::TokenType};
+
+use super::Interpreter;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polynomial(Vec<f64>);
+impl Polynomial {
+    pub fn new(mut coefficients: Vec<f64>) -> Self {
+        let scale = coefficients.iter().fold(0f64, |acc, c| acc.max(c.abs()));
+        while coefficients
+            .last()
+            .is_some_and(|c| c.abs() <= scale * 1e-12)
+        {
+            coefficients.pop();
+        }
+        Self(coefficients)
+    }
+
+    pub fn constant(c: f64) -> Self {
+        Self::new(vec![c])
+    }
+
+    pub fn coefficients(&self) -> &[f64] {
+        &self.0
+    }
+
+    pub fn degree(&self) -> usize {
+        self.0.len().saturating_sub(1)
+    }
+
+    pub fn coefficient(&self, power: usize) -> f64 {
+        self.0.get(power).copied().unwrap_or(0f64)
+    }
+
+    pub fn powi(&self, n: u32) -> Self {
+        (0..n).fold(Self::constant(1f64), |acc, _| &acc * self)
+    }
+
+    pub fn scale(&self, c: f64) -> Self {
+        Self::new(self.0.iter().map(|a| a * c).collect())
+    }
+
+    pub fn eval(&self, x: f64) -> f64 {
+        self.0.iter().rev().fold(0f64, |acc, c| acc * x + c)
+    }
+
+    pub fn integral(&self) -> Self {
+        let mut coefficients = vec![0f64];
+        coefficients.extend(self.0.iter().enumerate().map(|(i, c)| c / (i + 1) as f64));
+        Self::new(coefficients)
+    }
+
+    pub fn to_node(&self, var: char) -> Node {
+        self.to_node_at(&Node::Var(var))
+    }
+
+    pub fn to_node_at(&self, x: &Node) -> Node {
+        let terms = self
+            .0
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, c)| **c != 0f64)
+            .map(|(i, c)| {
+                let power = match i {
+                    0 => Node::Num(1f64),
+                    1 => x.clone(),
+                    _ => Node::Exponent {
+                        base: Box::new(x.clone()),
+                        exponent: Box::new(Node::Num(i as f64)),
+                    },
+                };
+                let term = match (i, c.abs()) {
+                    (0, c) => Node::Num(c),
+                    (_, 1f64) => power,
+                    (_, c) => Node::Factors(vec![
+                        (TokenType::Mul, Node::Num(c)),
+                        (TokenType::Mul, power),
+                    ]),
+                };
+                let sign = if *c < 0f64 {
+                    TokenType::Minus
+                } else {
+                    TokenType::Plus
+                };
+                (sign, term)
+            })
+            .collect::<Vec<_>>();
+        match &terms[..] {
+            [] => Node::Num(0f64),
+            [(TokenType::Plus, term)] => term.clone(),
+            _ => Node::Terms(terms),
+        }
+    }
+
+    pub fn divide_root(&self, root: f64) -> Self {
+        let mut quotient = vec![0f64; self.degree()];
+        let mut carry = 0f64;
+        for (i, c) in self.0.iter().enumerate().skip(1).rev() {
+            carry = carry * root + c;
+            quotient[i - 1] = carry;
+        }
+        Self::new(quotient)
+    }
+}
+impl Add for &Polynomial {
+    type Output = Polynomial;
+
+    fn add(self, rhs: Self) -> Polynomial {
+        Polynomial::new(
+            (0..self.0.len().max(rhs.0.len()))
+                .map(|i| self.coefficient(i) + rhs.coefficient(i))
+                .collect(),
+        )
+    }
+}
+impl Neg for &Polynomial {
+    type Output = Polynomial;
+
+    fn neg(self) -> Polynomial {
+        self.scale(-1f64)
+    }
+}
+impl Sub for &Polynomial {
+    type Output = Polynomial;
+
+    fn sub(self, rhs: Self) -> Polynomial {
+        self + &-rhs
+    }
+}
+impl Mul for &Polynomial {
+    type Output = Polynomial;
+
+    fn mul(self, rhs: Self) -> Polynomial {
+        if self.0.is_empty() || rhs.0.is_empty() {
+            return Polynomial::new(vec![]);
+        }
+        let mut product = vec![0f64; self.0.len() + rhs.0.len() - 1];
+        for (i, a) in self.0.iter().enumerate() {
+            for (j, b) in rhs.0.iter().enumerate() {
+                product[i + j] += a * b;
+            }
+        }
+        Polynomial::new(product)
+    }
+}
+
+impl Interpreter {
+    pub fn polynomial(&self, node: &Node, var: char) -> Option<Polynomial> {
+        if !self.unknowns(node).contains(&var) {
+            return match self.visit(node, None).ok()? {
+                Node::Num(num) => Some(Polynomial::constant(num)),
+                _ => None,
+            };
+        }
+        match node {
+            Node::Var(_) => Some(Polynomial::new(vec![0f64, 1f64])),
+            Node::Exponent { base, exponent } => {
+                let base = self.polynomial(base, var)?;
+                match self.polynomial(exponent, var)?.coefficients() {
+                    [] => Some(Polynomial::constant(1f64)),
+                    [n] if *n >= 0f64 && n.fract() == 0f64 && *n <= 64f64 => {
+                        Some(base.powi(*n as u32))
+                    }
+                    _ => None,
+                }
+            }
+            Node::Factors(factors) => {
+                factors
+                    .iter()
+                    .try_fold(Polynomial::constant(1f64), |acc, (op, factor)| {
+                        let factor = self.polynomial(factor, var)?;
+                        match op {
+                            TokenType::Mul => Some(&acc * &factor),
+                            TokenType::Div => match factor.coefficients() {
+                                [c] => Some(acc.scale(1f64 / c)),
+                                _ => None,
+                            },
+                            _ => unreachable!(),
+                        }
+                    })
+            }
+            Node::Terms(terms) => {
+                terms
+                    .iter()
+                    .try_fold(Polynomial::new(vec![]), |acc, (op, term)| {
+                        let term = self.polynomial(term, var)?;
+                        match op {
+                            TokenType::Plus => Some(&acc + &term),
+                            TokenType::Minus => Some(&acc - &term),
+                            _ => unreachable!(),
+                        }
+                    })
+            }
+            Node::Derivative { .. } => self.polynomial(&self.visit(node, None).ok()?, var),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+
+use crate::node::Node;
+
+use super::{estimate, Interpreter, InterpreterError, InterpreterResult};
+
+const KRONROD_NODES: [f64; 8] = [
+    0.991_455_371_120_812_6,
+    0.949_107_912_342_758_5,
+    0.864_864_423_359_769_1,
+    0.741_531_185_599_394_4,
+    0.586_087_235_467_691_1,
+    0.405_845_151_377_397_2,
+    0.207_784_955_007_898_5,
+    0f64,
+];
+const KRONROD_WEIGHTS: [f64; 8] = [
+    0.022_935_322_010_529_22,
+    0.063_092_092_629_978_55,
+    0.104_790_010_322_250_2,
+    0.140_653_259_715_525_9,
+    0.169_004_726_639_267_9,
+    0.190_350_578_064_785_4,
+    0.204_432_940_075_298_9,
+    0.209_482_141_084_727_8,
+];
+const GAUSS_WEIGHTS: [f64; 4] = [
+    0.129_484_966_168_869_7,
+    0.279_705_391_489_276_7,
+    0.381_830_050_505_118_9,
+    0.417_959_183_673_469_4,
+];
+const TOLERANCE: f64 = 1e-10;
+const MAX_INTERVALS: usize = 2000;
+const MAX_DEPTH: u32 = 40;
+const MAX_ROWS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuadratureMethod {
+    GaussKronrod,
+    Simpson,
+    Romberg,
+}
+
+type Mapping = Box<dyn Fn(f64) -> (f64, f64)>;
+
+fn tolerance(value: f64) -> f64 {
+    (TOLERANCE * value.abs()).max(1e-12)
+}
+
+fn kronrod(g: &dyn Fn(f64) -> f64, a: f64, b: f64) -> (f64, f64) {
+    let (center, half) = ((a + b) / 2f64, (b - a) / 2f64);
+    let fc = g(center);
+    let mut kronrod = KRONROD_WEIGHTS[7] * fc;
+    let mut gauss = GAUSS_WEIGHTS[3] * fc;
+    for j in 0..7 {
+        let x = half * KRONROD_NODES[j];
+        let sum = g(center - x) + g(center + x);
+        kronrod += KRONROD_WEIGHTS[j] * sum;
+        if j % 2 == 1 {
+            gauss += GAUSS_WEIGHTS[j / 2] * sum;
+        }
+    }
+    (kronrod * half, ((kronrod - gauss) * half).abs())
+}
+
+fn gauss_kronrod(g: &dyn Fn(f64) -> f64, a: f64, b: f64) -> (f64, f64) {
+    let (value, error) = kronrod(g, a, b);
+    let mut intervals = vec![(a, b, value, error)];
+    loop {
+        let value = intervals.iter().map(|interval| interval.2).sum::<f64>();
+        let error = intervals.iter().map(|interval| interval.3).sum::<f64>();
+        if error <= tolerance(value) || !error.is_finite() || intervals.len() >= MAX_INTERVALS {
+            return (value, error);
+        }
+        let worst = (0..intervals.len())
+            .max_by(|i, j| intervals[*i].3.total_cmp(&intervals[*j].3))
+            .expect("at least one interval");
+        let (a, b, ..) = intervals[worst];
+        let mid = (a + b) / 2f64;
+        if mid == a || mid == b {
+            return (value, error);
+        }
+        intervals.swap_remove(worst);
+        for (a, b) in [(a, mid), (mid, b)] {
+            let (value, error) = kronrod(g, a, b);
+            intervals.push((a, b, value, error));
+        }
+    }
+}
+
+fn simpson_step(
+    g: &dyn Fn(f64) -> f64,
+    [(a, fa), (m, fm), (b, fb)]: [(f64, f64); 3],
+    whole: f64,
+    tol: f64,
+    depth: u32,
+) -> (f64, f64) {
+    let (l, r) = ((a + m) / 2f64, (m + b) / 2f64);
+    let (fl, fr) = (g(l), g(r));
+    let left = (m - a) / 6f64 * (fa + 4f64 * fl + fm);
+    let right = (b - m) / 6f64 * (fm + 4f64 * fr + fb);
+    let delta = left + right - whole;
+    if depth == 0 || delta.abs() <= 15f64 * tol || !delta.is_finite() {
+        return (left + right + delta / 15f64, delta.abs() / 15f64);
+    }
+    let (left, left_error) =
+        simpson_step(g, [(a, fa), (l, fl), (m, fm)], left, tol / 2f64, depth - 1);
+    let (right, right_error) =
+        simpson_step(g, [(m, fm), (r, fr), (b, fb)], right, tol / 2f64, depth - 1);
+    (left + right, left_error + right_error)
+}
+
+fn simpson(g: &dyn Fn(f64) -> f64, a: f64, b: f64) -> (f64, f64) {
+    let m = (a + b) / 2f64;
+    let (fa, fm, fb) = (g(a), g(m), g(b));
+    let whole = (b - a) / 6f64 * (fa + 4f64 * fm + fb);
+    simpson_step(
+        g,
+        [(a, fa), (m, fm), (b, fb)],
+        whole,
+        tolerance(whole),
+        MAX_DEPTH,
+    )
+}
+
+fn romberg(g: &dyn Fn(f64) -> f64, a: f64, b: f64) -> (f64, f64) {
+    let mut h = b - a;
+    let mut previous = vec![h * (g(a) + g(b)) / 2f64];
+    let mut error = f64::INFINITY;
+    for i in 1..MAX_ROWS {
+        let sum = (0..1usize << (i - 1))
+            .map(|k| g(a + (2 * k + 1) as f64 * h / 2f64))
+            .sum::<f64>();
+        let mut row = vec![previous[0] / 2f64 + h / 2f64 * sum];
+        h /= 2f64;
+        for j in 1..=i {
+            let factor = 4f64.powi(j as i32);
+            row.push(row[j - 1] + (row[j - 1] - previous[j - 1]) / (factor - 1f64));
+        }
+        error = (row[i] - previous[i - 1]).abs();
+        previous = row;
+        if i > 4 && error <= tolerance(previous[i]) {
+            break;
+        }
+    }
+    (previous[previous.len() - 1], error)
+}
+
+impl Interpreter {
+    fn mapping(&self, value: &dyn Fn(f64) -> f64, a: f64, b: f64) -> (f64, f64, Mapping) {
+        let singular = |x: f64| !value(x).is_finite();
+        match (a.is_finite(), b.is_finite()) {
+            (true, true) if singular(a) || singular(b) => (
+                0f64,
+                1f64,
+                Box::new(move |t| {
+                    (
+                        a + (b - a) * t * t * (3f64 - 2f64 * t),
+                        6f64 * (b - a) * t * (1f64 - t),
+                    )
+                }),
+            ),
+            (true, true) => (a, b, Box::new(|x| (x, 1f64))),
+            (true, false) => (
+                0f64,
+                1f64,
+                Box::new(move |t| (a + t / (1f64 - t), 1f64 / (1f64 - t).powi(2))),
+            ),
+            (false, true) => (
+                0f64,
+                1f64,
+                Box::new(move |t| (b - (1f64 - t) / t, 1f64 / (t * t))),
+            ),
+            (false, false) => (
+                -1f64,
+                1f64,
+                Box::new(|t| (t / (1f64 - t * t), (1f64 + t * t) / (1f64 - t * t).powi(2))),
+            ),
+        }
+    }
+
+    pub fn definite_integral(
+        &self,
+        expr: &Node,
+        var: char,
+        a: f64,
+        b: f64,
+        at: &HashMap<char, f64>,
+    ) -> InterpreterResult<Node> {
+        if a == b {
+            return Ok(Node::Num(0f64));
+        }
+        if a > b {
+            let (value, error) = self
+                .definite_integral(expr, var, b, a, at)?
+                .as_estimate()
+                .expect("definite integral is numeric");
+            return Ok(estimate(-value, error));
+        }
+        let value = |x: f64| {
+            let mut at = at.clone();
+            at.insert(var, x);
+            self.evaluate(expr, &at).unwrap_or(f64::NAN)
+        };
+        let (lo, hi, map) = self.mapping(&value, a, b);
+        let sample = |t: f64| {
+            let (x, weight) = map(t);
+            value(x) * weight
+        };
+        let g = |t: f64| {
+            let value = sample(t);
+            if value.is_finite() || (t != lo && t != hi) {
+                return value;
+            }
+            let nudge = 1e-9 * (hi - lo);
+            sample(if t == lo { t + nudge } else { t - nudge })
+        };
+        let (value, error) = match self.quadrature_method {
+            QuadratureMethod::GaussKronrod => gauss_kronrod(&g, lo, hi),
+            QuadratureMethod::Simpson => simpson(&g, lo, hi),
+            QuadratureMethod::Romberg => romberg(&g, lo, hi),
+        };
+        if value.is_nan() {
+            Err(InterpreterError::Undefined)
+        } else if value == f64::INFINITY {
+            Err(InterpreterError::Infinity)
+        } else if value == f64::NEG_INFINITY {
+            Err(InterpreterError::NegInfinity)
+        } else {
+            Ok(estimate(value, error))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QuadratureMethod;
+    use crate::{
+        interpreter::{parse, run, Interpreter},
+        node::Node,
+    };
+
+    fn value(line: &str) -> f64 {
+        run(line).unwrap().as_estimate().unwrap().0
+    }
+
+    #[test]
+    fn gaussian_over_the_real_line() {
+        let integral = value("integrate(e^(-x^2), x, -∞, ∞)");
+        assert!((integral - std::f64::consts::PI.sqrt()).abs() < 1e-10);
+    }
+
+    #[test]
+    fn endpoint_singularity() {
+        assert!((value("integrate(1/sqrt(x), x, 0, 1)") - 2f64).abs() < 1e-8);
+    }
+
+    #[test]
+    fn every_method_integrates_a_polynomial() {
+        for method in [
+            QuadratureMethod::GaussKronrod,
+            QuadratureMethod::Simpson,
+            QuadratureMethod::Romberg,
+        ] {
+            let mut interpreter = Interpreter::new();
+            interpreter.set_quadrature_method(method);
+            let (integral, _) = interpreter
+                .visit(&parse("integrate(x^3, x, 0, 2)"), None)
+                .unwrap()
+                .as_estimate()
+                .unwrap();
+            assert!((integral - 4f64).abs() < 1e-9, "{method:?}: {integral}");
+        }
+    }
+
+    #[test]
+    fn estimates_take_part_in_arithmetic() {
+        assert_eq!(run("integrate(x, x, 0, 1) + 1").unwrap(), Node::Num(1.5));
+        assert_eq!(run("integrate(x, x, 1, 0)").unwrap(), Node::Num(-0.5));
+        assert!((value("2*integrate(x^2, x, 0, 1)") - 2f64 / 3f64).abs() < 1e-12);
+        let (value, error) = run("integrate(1/sqrt(x), x, 0, 1)^2")
+            .unwrap()
+            .as_estimate()
+            .unwrap();
+        assert!((value - 4f64).abs() < 1e-8 && error.is_finite());
+    }
+}
@@ -0,0 +1,114 @@
+use crate::{node::Node, token::TokenType};
+
+pub fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rational {
+    num: i64,
+    den: i64,
+}
+impl Rational {
+    pub fn new(num: i64, den: i64) -> Self {
+        let g = gcd(num, den) * den.signum();
+        Self {
+            num: num / g,
+            den: den / g,
+        }
+    }
+
+    fn checked(num: i128, den: i128) -> Option<Self> {
+        let g = {
+            let (mut a, mut b) = (num.abs(), den.abs());
+            while b != 0 {
+                (a, b) = (b, a % b);
+            }
+            a * den.signum()
+        };
+        Some(Self {
+            num: (num / g).try_into().ok()?,
+            den: (den / g).try_into().ok()?,
+        })
+    }
+
+    pub fn from_f64(n: f64) -> Option<Self> {
+        if !n.is_finite() || n.abs() > 1e15 {
+            return None;
+        }
+        let (mut h0, mut h1, mut k0, mut k1) = (0i64, 1i64, 1i64, 0i64);
+        let mut x = n;
+        for _ in 0..64 {
+            let a = x.floor();
+            (h0, h1) = (h1, h1.checked_mul(a as i64)?.checked_add(h0)?);
+            (k0, k1) = (k1, k1.checked_mul(a as i64)?.checked_add(k0)?);
+            if k1 > 1_000_000_000 {
+                return None;
+            }
+            if (h1 as f64 / k1 as f64 - n).abs() <= 1e-12 * n.abs().max(1f64) {
+                return Some(Self::new(h1, k1));
+            }
+            x = 1f64 / (x - a);
+        }
+        None
+    }
+
+    pub fn numerator(self) -> i64 {
+        self.num
+    }
+
+    pub fn denominator(self) -> i64 {
+        self.den
+    }
+
+    pub fn signum(self) -> f64 {
+        self.num.signum() as f64
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.num == 0
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        Self::checked(
+            self.num as i128 * rhs.den as i128 + rhs.num as i128 * self.den as i128,
+            self.den as i128 * rhs.den as i128,
+        )
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.checked_add(Self::new(-rhs.num, rhs.den))
+    }
+
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        Self::checked(
+            self.num as i128 * rhs.num as i128,
+            self.den as i128 * rhs.den as i128,
+        )
+    }
+
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        if rhs.is_zero() {
+            return None;
+        }
+        Self::checked(
+            self.num as i128 * rhs.den as i128,
+            self.den as i128 * rhs.num as i128,
+        )
+    }
+
+    pub fn to_node(self) -> Node {
+        if self.den == 1 {
+            Node::Num(self.num as f64)
+        } else {
+            Node::Factors(vec![
+                (TokenType::Mul, Node::Num(self.num as f64)),
+                (TokenType::Div, Node::Num(self.den as f64)),
+            ])
+        }
+    }
+}
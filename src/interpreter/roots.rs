@@ -0,0 +1,179 @@
+use crate::node::Node;
+
+use super::{
+    complex::Complex, polynomial::Polynomial, Interpreter, InterpreterError, InterpreterResult,
+};
+
+impl Polynomial {
+    pub fn eval_complex(&self, z: Complex) -> Complex {
+        self.coefficients()
+            .iter()
+            .rev()
+            .fold(Complex::new(0f64, 0f64), |acc, c| {
+                acc * z + Complex::new(*c, 0f64)
+            })
+    }
+
+    pub fn derivative(&self) -> Self {
+        Polynomial::new(
+            self.coefficients()
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(i, c)| c * i as f64)
+                .collect(),
+        )
+    }
+
+    fn polish(&self, mut z: Complex) -> Complex {
+        let derivative = self.derivative();
+        for _ in 0..50 {
+            let delta = self.eval_complex(z) / derivative.eval_complex(z);
+            if !delta.re.is_finite() || !delta.im.is_finite() {
+                break;
+            }
+            z = z - delta;
+            if delta.abs() <= 1e-15 * z.abs().max(1f64) {
+                break;
+            }
+        }
+        z
+    }
+
+    fn is_root(&self, z: Complex) -> bool {
+        let scale = self
+            .coefficients()
+            .iter()
+            .rev()
+            .fold(0f64, |acc, c| acc * z.abs() + c.abs());
+        self.eval_complex(z).abs() <= 1e-9 * scale
+    }
+
+    pub fn roots(&self) -> Vec<Complex> {
+        let zeros = self
+            .coefficients()
+            .iter()
+            .take_while(|c| **c == 0f64)
+            .count();
+        let mut roots = vec![Complex::new(0f64, 0f64); zeros];
+        let reduced = Polynomial::new(self.coefficients()[zeros..].to_vec());
+        let degree = reduced.degree();
+        if degree == 0 {
+            return roots;
+        }
+        let monic = reduced.scale(1f64 / reduced.coefficient(degree));
+        let seed = Complex::new(0.4, 0.9);
+        let mut estimates = (0..degree)
+            .scan(Complex::new(1f64, 0f64), |z, _| {
+                *z = *z * seed;
+                Some(*z)
+            })
+            .collect::<Vec<_>>();
+        for _ in 0..1000 {
+            let mut change = 0f64;
+            for i in 0..degree {
+                let denom = estimates
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .fold(Complex::new(1f64, 0f64), |acc, (_, z)| {
+                        acc * (estimates[i] - *z)
+                    });
+                let delta = monic.eval_complex(estimates[i]) / denom;
+                if delta.re.is_finite() && delta.im.is_finite() {
+                    estimates[i] = estimates[i] - delta;
+                    change = change.max(delta.abs() / estimates[i].abs().max(1f64));
+                }
+            }
+            if change < 1e-14 {
+                break;
+            }
+        }
+        let mut clusters: Vec<(Complex, Vec<Complex>)> = vec![];
+        for z in estimates {
+            match clusters
+                .iter_mut()
+                .find(|(mean, _)| (*mean - z).abs() < 1e-3 * z.abs().max(1f64))
+            {
+                Some((mean, members)) => {
+                    members.push(z);
+                    let weight = Complex::new(1f64 / members.len() as f64, 0f64);
+                    *mean = *mean + (z - *mean) * weight;
+                }
+                None => clusters.push((z, vec![z])),
+            }
+        }
+        for (mean, members) in clusters {
+            let n = members.len();
+            let refined = (1..n)
+                .fold(monic.clone(), |poly, _| poly.derivative())
+                .polish(mean);
+            let mut found = if n > 1 && monic.is_root(refined) {
+                vec![refined; n]
+            } else {
+                members.into_iter().map(|z| monic.polish(z)).collect()
+            };
+            for z in &mut found {
+                if z.im.abs() < 1e-10 * z.abs().max(1f64) {
+                    z.im = 0f64;
+                }
+            }
+            roots.append(&mut found);
+        }
+        roots.sort_by(|a, b| a.re.total_cmp(&b.re).then(a.im.total_cmp(&b.im)));
+        roots
+    }
+}
+
+impl Interpreter {
+    pub fn roots(&self, node: &Node, var: char) -> InterpreterResult<Vec<Complex>> {
+        let f = match node {
+            Node::Equation { .. } => self.move_equation(node)?,
+            _ => node.clone(),
+        };
+        let poly = self
+            .polynomial(&f, var)
+            .ok_or_else(|| InterpreterError::SolveError(format!("Not a polynomial in {var}")))?;
+        if poly.coefficients().is_empty() {
+            return Err(InterpreterError::SolveError(String::from(
+                "Infinitely many solutions",
+            )));
+        }
+        Ok(poly.roots())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::{parse, run, Interpreter};
+
+    fn run_complex(line: &str) -> String {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_complex(true);
+        interpreter.visit(&parse(line), None).unwrap().to_string()
+    }
+
+    #[test]
+    fn complex_conjugate_roots() {
+        assert_eq!(
+            run("roots(x^2+2x+5, x)").unwrap().to_string(),
+            "[-1-2*i, -1+2*i]"
+        );
+    }
+
+    #[test]
+    fn roots_can_be_evaluated_again_in_complex_mode() {
+        assert_eq!(run_complex("(-1+2i)^2+2(-1+2i)+5"), "0");
+        assert_eq!(run_complex("(1+2i)*(1-2i)"), "5");
+        assert_eq!(run_complex("i^2"), "-1");
+        assert_eq!(run("i^2").unwrap().to_string(), "i^2");
+    }
+
+    #[test]
+    fn real_roots_of_quartic() {
+        assert_eq!(
+            run("roots(x^4-5x^2+4, x)").unwrap().to_string(),
+            "[-2, -1, 1, 2]"
+        );
+    }
+}
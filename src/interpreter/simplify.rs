@@ -0,0 +1,417 @@
+use std::{cmp::Ordering, f64::consts::E};
+
+use crate::{
+    node::Node,
+    token::{FuncType, TokenType},
+};
+
+use super::Interpreter;
+
+fn rank(node: &Node) -> u8 {
+    match node {
+        Node::Num(_) => 0,
+        Node::Var(_) => 1,
+        Node::Func { .. } => 2,
+        Node::Terms(_) => 3,
+        _ => 4,
+    }
+}
+
+fn compare_bases(a: &Node, b: &Node) -> Ordering {
+    rank(a)
+        .cmp(&rank(b))
+        .then_with(|| a.to_string().cmp(&b.to_string()))
+}
+
+fn degree(node: &Node) -> f64 {
+    match node {
+        Node::Var(_) => 1f64,
+        Node::Exponent { base, exponent } => match (base.as_ref(), exponent.as_ref()) {
+            (Node::Var(_), Node::Num(k)) => *k,
+            _ => 0f64,
+        },
+        Node::Factors(factors) => factors
+            .iter()
+            .map(|(op, factor)| match op {
+                TokenType::Div => -degree(factor),
+                _ => degree(factor),
+            })
+            .sum(),
+        _ => 0f64,
+    }
+}
+
+fn split_coefficient(node: Node) -> (f64, Option<Node>) {
+    match node {
+        Node::Num(n) => (n, None),
+        Node::Terms(terms) if terms.len() == 1 => {
+            let (op, term) = terms.into_iter().next().expect("length checked");
+            let (c, rest) = split_coefficient(term);
+            match op {
+                TokenType::Minus => (-c, rest),
+                _ => (c, rest),
+            }
+        }
+        Node::Factors(mut factors)
+            if matches!(factors.first(), Some((TokenType::Mul, Node::Num(_)))) =>
+        {
+            let Node::Num(c) = factors.remove(0).1 else {
+                unreachable!()
+            };
+            let rest = match &factors[..] {
+                [(TokenType::Mul, factor)] => factor.clone(),
+                _ => Node::Factors(factors),
+            };
+            (c, Some(rest))
+        }
+        node => (1f64, Some(node)),
+    }
+}
+
+fn scaled(c: f64, rest: Option<Node>) -> Node {
+    let magnitude = c.abs();
+    let node = match rest {
+        None => Node::Num(magnitude),
+        Some(rest) if magnitude == 1f64 => rest,
+        Some(Node::Factors(factors)) => {
+            let mut scaled = vec![(TokenType::Mul, Node::Num(magnitude))];
+            scaled.extend(factors);
+            Node::Factors(scaled)
+        }
+        Some(rest) => Node::Factors(vec![
+            (TokenType::Mul, Node::Num(magnitude)),
+            (TokenType::Mul, rest),
+        ]),
+    };
+    if c < 0f64 {
+        Node::Terms(vec![(TokenType::Minus, node)])
+    } else {
+        node
+    }
+}
+
+fn power(base: Node, exponent: Node) -> Node {
+    let base = match base {
+        Node::Num(n) if n == E => Node::Var('e'),
+        base => base,
+    };
+    if let (
+        Node::Var('e'),
+        Node::Func {
+            func: FuncType::Ln,
+            arg,
+        },
+    ) = (&base, &exponent)
+    {
+        return *arg.clone();
+    }
+    if exponent == Node::Num(1f64) {
+        base
+    } else {
+        Node::Exponent {
+            base: Box::new(base),
+            exponent: Box::new(exponent),
+        }
+    }
+}
+
+fn summands(node: Node) -> Vec<Node> {
+    match node {
+        Node::Terms(terms) => terms
+            .into_iter()
+            .map(|(op, term)| match op {
+                TokenType::Minus => Node::Terms(vec![(op, term)]),
+                _ => term,
+            })
+            .collect(),
+        node => vec![node],
+    }
+}
+
+impl Interpreter {
+    pub fn simplify(&self, node: &Node) -> Node {
+        match node {
+            Node::Func { func, arg } => {
+                let arg = self.simplify(arg);
+                match arg {
+                    Node::Num(_) => self.visit_func(func, &arg, None).unwrap_or(Node::Func {
+                        func: func.clone(),
+                        arg: Box::new(arg),
+                    }),
+                    arg => Node::Func {
+                        func: func.clone(),
+                        arg: Box::new(arg),
+                    },
+                }
+            }
+            Node::Exponent { base, exponent } => {
+                let node = Node::Exponent {
+                    base: Box::new(self.simplify(base)),
+                    exponent: Box::new(self.simplify(exponent)),
+                };
+                self.product(&[(TokenType::Mul, node.clone())])
+                    .unwrap_or(node)
+            }
+            Node::Factors(factors) => {
+                let factors = factors
+                    .iter()
+                    .map(|(op, factor)| (*op, self.simplify(factor)))
+                    .collect::<Vec<_>>();
+                self.product(&factors).unwrap_or(Node::Factors(factors))
+            }
+            Node::Terms(terms) => self.sum(
+                terms
+                    .iter()
+                    .map(|(op, term)| (*op, self.simplify(term)))
+                    .collect(),
+            ),
+            Node::Equation { lhs, rhs } => Node::Equation {
+                lhs: Box::new(self.simplify(lhs)),
+                rhs: Box::new(self.simplify(rhs)),
+            },
+            Node::Vector(nodes) => {
+                Node::Vector(nodes.iter().map(|node| self.simplify(node)).collect())
+            }
+            Node::System(nodes) => {
+                Node::System(nodes.iter().map(|node| self.simplify(node)).collect())
+            }
+            _ => node.clone(),
+        }
+    }
+
+    pub fn expand(&self, node: &Node) -> Node {
+        match node {
+            Node::Terms(terms) => self.sum(
+                terms
+                    .iter()
+                    .map(|(op, term)| (*op, self.expand(term)))
+                    .collect(),
+            ),
+            Node::Factors(factors) => {
+                let mut numerator = vec![Node::Num(1f64)];
+                let mut denominator = vec![];
+                for (op, factor) in factors {
+                    let factor = self.expand(factor);
+                    match op {
+                        TokenType::Div => denominator.push((TokenType::Div, factor)),
+                        _ => numerator = self.distribute(&numerator, &summands(factor)),
+                    }
+                }
+                self.sum(
+                    numerator
+                        .into_iter()
+                        .map(|term| {
+                            let mut factors = vec![(TokenType::Mul, term)];
+                            factors.extend(denominator.iter().cloned());
+                            let product = self.product(&factors);
+                            (TokenType::Plus, product.unwrap_or(Node::Factors(factors)))
+                        })
+                        .collect(),
+                )
+            }
+            Node::Exponent { base, exponent } => match (self.expand(base), self.simplify(exponent))
+            {
+                (base @ Node::Terms(_), Node::Num(n))
+                    if n.fract() == 0f64 && (2f64..=64f64).contains(&n) =>
+                {
+                    let base = summands(base);
+                    let terms =
+                        (1..n as usize).fold(base.clone(), |acc, _| self.distribute(&acc, &base));
+                    self.sum(
+                        terms
+                            .into_iter()
+                            .map(|term| (TokenType::Plus, term))
+                            .collect(),
+                    )
+                }
+                (base, exponent) => self.simplify(&Node::Exponent {
+                    base: Box::new(base),
+                    exponent: Box::new(exponent),
+                }),
+            },
+            Node::Func { func, arg } => self.simplify(&Node::Func {
+                func: func.clone(),
+                arg: Box::new(self.expand(arg)),
+            }),
+            Node::Equation { lhs, rhs } => Node::Equation {
+                lhs: Box::new(self.expand(lhs)),
+                rhs: Box::new(self.expand(rhs)),
+            },
+            Node::Vector(nodes) => {
+                Node::Vector(nodes.iter().map(|node| self.expand(node)).collect())
+            }
+            Node::System(nodes) => {
+                Node::System(nodes.iter().map(|node| self.expand(node)).collect())
+            }
+            _ => self.simplify(node),
+        }
+    }
+
+    fn distribute(&self, lhs: &[Node], rhs: &[Node]) -> Vec<Node> {
+        let terms = lhs
+            .iter()
+            .flat_map(|a| {
+                rhs.iter().map(move |b| {
+                    let factors = [(TokenType::Mul, a.clone()), (TokenType::Mul, b.clone())];
+                    (
+                        TokenType::Plus,
+                        self.product(&factors)
+                            .unwrap_or(Node::Factors(factors.to_vec())),
+                    )
+                })
+            })
+            .collect();
+        summands(self.sum(terms))
+    }
+
+    fn collect(&self, node: &Node, power: f64, coefficient: &mut f64, out: &mut Vec<(Node, Node)>) {
+        let integral = power.fract() == 0f64;
+        match node {
+            Node::Num(n) => *coefficient *= n.powf(power),
+            Node::Factors(factors) if integral => {
+                for (op, factor) in factors {
+                    let sign = if *op == TokenType::Div { -1f64 } else { 1f64 };
+                    self.collect(factor, sign * power, coefficient, out);
+                }
+            }
+            Node::Terms(terms) if integral && terms.len() == 1 => {
+                if terms[0].0 == TokenType::Minus {
+                    *coefficient *= (-1f64).powf(power);
+                }
+                self.collect(&terms[0].1, power, coefficient, out);
+            }
+            Node::Exponent { base, exponent } => match exponent.as_ref() {
+                Node::Num(k) if integral => self.collect(base, k * power, coefficient, out),
+                Node::Num(k) if base.as_ref() != &Node::Num(0f64) => {
+                    out.push((*base.clone(), Node::Num(k * power)))
+                }
+                exponent => out.push((
+                    *base.clone(),
+                    if power == 1f64 {
+                        exponent.clone()
+                    } else {
+                        self.product(&[
+                            (TokenType::Mul, Node::Num(power)),
+                            (TokenType::Mul, exponent.clone()),
+                        ])
+                        .expect("finite coefficient")
+                    },
+                )),
+            },
+            node => out.push((node.clone(), Node::Num(power))),
+        }
+    }
+
+    fn product(&self, factors: &[(TokenType, Node)]) -> Option<Node> {
+        let mut coefficient = 1f64;
+        let mut collected = vec![];
+        for (op, factor) in factors {
+            let sign = if *op == TokenType::Div { -1f64 } else { 1f64 };
+            self.collect(factor, sign, &mut coefficient, &mut collected);
+        }
+        let mut groups: Vec<(Node, Node)> = vec![];
+        for (base, exponent) in collected {
+            match groups.iter_mut().find(|(b, _)| *b == base) {
+                Some((_, existing)) => {
+                    *existing = match (&*existing, &exponent) {
+                        (Node::Num(a), Node::Num(b)) => Node::Num(a + b),
+                        _ => self.sum(vec![
+                            (TokenType::Plus, existing.clone()),
+                            (TokenType::Plus, exponent),
+                        ]),
+                    }
+                }
+                None => groups.push((base, exponent)),
+            }
+        }
+        let mut numerator = vec![];
+        let mut denominator = vec![];
+        for (base, exponent) in groups {
+            match (&base, &exponent) {
+                (_, Node::Num(k)) if *k == 0f64 => {}
+                (Node::Num(b), Node::Num(k)) => coefficient *= b.powf(*k),
+                (_, Node::Num(k)) if *k < 0f64 => denominator.push((base, Node::Num(-k))),
+                _ => numerator.push((base, exponent)),
+            }
+        }
+        if !coefficient.is_finite() {
+            return None;
+        }
+        if coefficient == 0f64 {
+            return Some(Node::Num(0f64));
+        }
+        numerator.sort_by(|a, b| compare_bases(&a.0, &b.0));
+        denominator.sort_by(|a, b| compare_bases(&a.0, &b.0));
+        let mut product = numerator
+            .into_iter()
+            .map(|(base, exponent)| (TokenType::Mul, power(base, exponent)))
+            .collect::<Vec<_>>();
+        product.extend(
+            denominator
+                .into_iter()
+                .map(|(base, exponent)| (TokenType::Div, power(base, exponent))),
+        );
+        let rest = match &product[..] {
+            [] => None,
+            [(TokenType::Mul, factor)] => Some(factor.clone()),
+            _ => Some(Node::Factors(product)),
+        };
+        Some(scaled(coefficient, rest))
+    }
+
+    fn sum(&self, terms: Vec<(TokenType, Node)>) -> Node {
+        let mut constant = 0f64;
+        let mut groups: Vec<(f64, Node)> = vec![];
+        let mut flattened = vec![];
+        for (op, term) in terms {
+            let sign = if op == TokenType::Minus { -1f64 } else { 1f64 };
+            match term {
+                Node::Terms(inner) if inner.len() > 1 => {
+                    flattened.extend(inner.into_iter().map(|(op, term)| {
+                        let inner_sign = if op == TokenType::Minus { -1f64 } else { 1f64 };
+                        (sign * inner_sign, term)
+                    }))
+                }
+                term => flattened.push((sign, term)),
+            }
+        }
+        for (sign, term) in flattened {
+            match split_coefficient(term) {
+                (c, None) => constant += sign * c,
+                (c, Some(rest)) => match groups.iter_mut().find(|(_, r)| *r == rest) {
+                    Some((existing, _)) => *existing += sign * c,
+                    None => groups.push((sign * c, rest)),
+                },
+            }
+        }
+        groups.retain(|(c, _)| *c != 0f64);
+        groups.sort_by(|(_, a), (_, b)| {
+            degree(b)
+                .total_cmp(&degree(a))
+                .then_with(|| a.to_string().cmp(&b.to_string()))
+        });
+        let mut terms = groups
+            .into_iter()
+            .map(|(c, rest)| {
+                let op = if c < 0f64 {
+                    TokenType::Minus
+                } else {
+                    TokenType::Plus
+                };
+                (op, scaled(c.abs(), Some(rest)))
+            })
+            .collect::<Vec<_>>();
+        if constant != 0f64 || terms.is_empty() {
+            let op = if constant < 0f64 {
+                TokenType::Minus
+            } else {
+                TokenType::Plus
+            };
+            terms.push((op, Node::Num(constant.abs())));
+        }
+        match &terms[..] {
+            [(TokenType::Plus, term)] => term.clone(),
+            _ => Node::Terms(terms),
+        }
+    }
+}
@@ -0,0 +1,422 @@
+use std::{collections::HashMap, f64::consts::PI};
+
+use crate::{
+    node::Node,
+    token::{FuncType, TokenType},
+};
+
+use super::{
+    newton::SystemMethod,
+    polynomial::Polynomial,
+    rational::{gcd, Rational},
+    Interpreter, InterpreterError, InterpreterResult,
+};
+
+fn divisors(n: i64) -> Vec<i64> {
+    let n = n.abs();
+    (1..=n)
+        .take_while(|d| d * d <= n)
+        .filter(|d| n % d == 0)
+        .flat_map(|d| [d, n / d])
+        .collect()
+}
+
+fn integral(poly: &Polynomial) -> Option<Vec<i64>> {
+    poly.coefficients()
+        .iter()
+        .map(|c| (c.fract() == 0f64 && c.abs() < 1e12).then_some(*c as i64))
+        .collect()
+}
+
+fn fraction(num: i64, den: i64) -> Node {
+    Rational::new(num, den).to_node()
+}
+
+fn quadratic(a: f64, b: f64, c: f64) -> Vec<Node> {
+    let disc = b * b - 4f64 * a * c;
+    if disc < -1e-12 * b.abs().max(1f64) {
+        return vec![];
+    }
+    let root = disc.max(0f64).sqrt();
+    let exact = [a, b, c].iter().all(|n| n.fract() == 0f64 && n.abs() < 1e6);
+    if !exact {
+        return if root == 0f64 {
+            vec![Node::Num(-b / (2f64 * a))]
+        } else {
+            let mut roots = [(-b - root) / (2f64 * a), (-b + root) / (2f64 * a)];
+            roots.sort_by(f64::total_cmp);
+            roots.into_iter().map(Node::Num).collect()
+        };
+    }
+    let (num, den) = (-b as i64, 2 * a as i64);
+    if root.fract() == 0f64 {
+        let root = root as i64;
+        let mut roots = vec![fraction(num - root, den)];
+        if root != 0 {
+            roots.push(fraction(num + root, den));
+        }
+        return roots;
+    }
+    let disc = disc as i64;
+    let square = (1..)
+        .take_while(|k| k * k <= disc)
+        .filter(|k| disc % (k * k) == 0)
+        .last()
+        .expect("1 divides every integer");
+    let radicand = disc / (square * square);
+    let g = gcd(gcd(num, square), den) * den.signum();
+    let (num, coef, den) = (num / g, square / g.abs(), den / g);
+    [TokenType::Minus, TokenType::Plus]
+        .into_iter()
+        .map(|sign| {
+            let sqrt = Node::Func {
+                func: FuncType::Sqrt,
+                arg: Box::new(Node::Num(radicand as f64)),
+            };
+            let irrational = if coef == 1 {
+                sqrt
+            } else {
+                Node::Factors(vec![
+                    (TokenType::Mul, Node::Num(coef as f64)),
+                    (TokenType::Mul, sqrt),
+                ])
+            };
+            let numerator = if num == 0 {
+                Node::Terms(vec![(sign, irrational)])
+            } else {
+                Node::Terms(vec![
+                    (TokenType::Plus, Node::Num(num as f64)),
+                    (sign, irrational),
+                ])
+            };
+            if den == 1 {
+                numerator
+            } else {
+                Node::Factors(vec![
+                    (TokenType::Mul, numerator),
+                    (TokenType::Div, Node::Num(den as f64)),
+                ])
+            }
+        })
+        .collect()
+}
+
+pub fn cubic_roots(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    let (b, c, d) = (b / a, c / a, d / a);
+    let p = c - b * b / 3f64;
+    let q = 2f64 * b.powi(3) / 27f64 - b * c / 3f64 + d;
+    let shift = -b / 3f64;
+    let disc = (q / 2f64).powi(2) + (p / 3f64).powi(3);
+    let scale = (q / 2f64).powi(2).max((p / 3f64).powi(3).abs());
+    if disc.abs() <= 1e-12 * scale {
+        if p == 0f64 {
+            vec![shift]
+        } else {
+            vec![3f64 * q / p + shift, -3f64 * q / (2f64 * p) + shift]
+        }
+    } else if disc > 0f64 || p >= 0f64 {
+        let root = disc.max(0f64).sqrt();
+        vec![(-q / 2f64 + root).cbrt() + (-q / 2f64 - root).cbrt() + shift]
+    } else {
+        let r = 2f64 * (-p / 3f64).sqrt();
+        let phi = ((3f64 * q) / (p * r)).clamp(-1f64, 1f64).acos() / 3f64;
+        (0..3)
+            .map(|k| r * (phi - 2f64 * PI * k as f64 / 3f64).cos() + shift)
+            .collect()
+    }
+}
+
+fn quartic_roots(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+    let p = c - 3f64 * b * b / 8f64;
+    let q = b.powi(3) / 8f64 - b * c / 2f64 + d;
+    let r = -3f64 * b.powi(4) / 256f64 + b * b * c / 16f64 - b * d / 4f64 + e;
+    let shift = -b / 4f64;
+    let real_quadratic = |a: f64, b: f64, c: f64| {
+        let disc = b * b - 4f64 * a * c;
+        if disc < 0f64 {
+            vec![]
+        } else {
+            vec![
+                (-b - disc.sqrt()) / (2f64 * a),
+                (-b + disc.sqrt()) / (2f64 * a),
+            ]
+        }
+    };
+    let roots = if q.abs() < 1e-12 {
+        real_quadratic(1f64, p, r)
+            .into_iter()
+            .filter(|z| *z >= 0f64)
+            .flat_map(|z| [-z.sqrt(), z.sqrt()])
+            .collect::<Vec<_>>()
+    } else {
+        let m = cubic_roots(8f64, 8f64 * p, 2f64 * p * p - 8f64 * r, -q * q)
+            .into_iter()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let s = (2f64 * m).sqrt();
+        let mut roots = real_quadratic(1f64, -s, p / 2f64 + m + q / (2f64 * s));
+        roots.append(&mut real_quadratic(1f64, s, p / 2f64 + m - q / (2f64 * s)));
+        roots
+    };
+    roots.into_iter().map(|y| y + shift).collect()
+}
+
+fn approximate(poly: &Polynomial, roots: Vec<f64>) -> Vec<Node> {
+    let slope = |x: f64| {
+        poly.coefficients()
+            .iter()
+            .enumerate()
+            .skip(1)
+            .rev()
+            .fold(0f64, |acc, (i, c)| acc * x + i as f64 * c)
+    };
+    roots
+        .into_iter()
+        .map(|x| {
+            let step = (poly.eval(x) / slope(x)).abs();
+            let error = if step.is_finite() {
+                step.max(f64::EPSILON * x.abs().max(1f64))
+            } else {
+                f64::EPSILON.sqrt() * x.abs().max(1f64)
+            };
+            Node::Estimate { value: x, error }
+        })
+        .collect()
+}
+
+impl Interpreter {
+    fn rational_root(&self, poly: &Polynomial) -> Option<(i64, i64)> {
+        let coefficients = integral(poly)?;
+        let leading = *coefficients.last()?;
+        if coefficients[0] == 0 {
+            return Some((0, 1));
+        }
+        let degree = coefficients.len() as u32 - 1;
+        let is_root = |p: i64, q: i64| {
+            coefficients
+                .iter()
+                .zip(0..)
+                .try_fold(0i128, |acc, (c, i)| {
+                    (*c as i128)
+                        .checked_mul((p as i128).checked_pow(i)?)?
+                        .checked_mul((q as i128).checked_pow(degree - i)?)?
+                        .checked_add(acc)
+                })
+                .is_some_and(|value| value == 0)
+        };
+        divisors(coefficients[0])
+            .into_iter()
+            .flat_map(|p| [p, -p])
+            .flat_map(|p| divisors(leading).into_iter().map(move |q| (p, q)))
+            .find(|(p, q)| gcd(*p, *q) == 1 && is_root(*p, *q))
+    }
+
+    fn solve_polynomial(&self, poly: &Polynomial) -> Option<Vec<Node>> {
+        let c = poly.coefficients();
+        match poly.degree() {
+            0 => Some(vec![]),
+            1 => Some(match integral(poly) {
+                Some(c) => vec![fraction(-c[0], c[1])],
+                None => vec![Node::Num(-c[0] / c[1])],
+            }),
+            2 => Some(quadratic(c[2], c[1], c[0])),
+            degree => {
+                if let Some((p, q)) = self.rational_root(poly) {
+                    let deflated = poly
+                        .divide_root(p as f64 / q as f64)
+                        .scale(1f64 / q as f64)
+                        .coefficients()
+                        .iter()
+                        .map(|c| c.round())
+                        .collect();
+                    let mut roots = self.solve_polynomial(&Polynomial::new(deflated))?;
+                    roots.push(fraction(p, q));
+                    Some(roots)
+                } else if degree == 3 {
+                    // Irreducible cubics with three real roots have no real radical form.
+                    Some(approximate(poly, cubic_roots(c[3], c[2], c[1], c[0])))
+                } else if degree == 4 {
+                    Some(approximate(
+                        poly,
+                        quartic_roots(c[4], c[3], c[2], c[1], c[0]),
+                    ))
+                } else {
+                    Some(approximate(
+                        poly,
+                        poly.roots()
+                            .into_iter()
+                            .filter(|z| z.im.abs() < 1e-9 * z.abs().max(1f64))
+                            .map(|z| z.re)
+                            .collect(),
+                    ))
+                }
+            }
+        }
+    }
+
+    pub fn solve_for(&self, eq: &Node, var: char) -> InterpreterResult<Vec<Node>> {
+        let f = self.move_equation(eq)?;
+        let slope = self.visit(&self.differentiate(&f, var, None)?, None)?;
+        if !self.unknowns(&slope).contains(&var) {
+            let intercept = self.visit(&f, Some(&HashMap::from([(var, 0f64)])))?;
+            if slope == Node::Num(0f64) {
+                return Err(InterpreterError::SolveError(String::from(
+                    if intercept == Node::Num(0f64) {
+                        "Infinitely many solutions"
+                    } else {
+                        "No solutions"
+                    },
+                )));
+            }
+            if let (Node::Num(a), Node::Num(b)) = (&slope, &intercept) {
+                if let Some(roots) = self.solve_polynomial(&Polynomial::new(vec![*b, *a])) {
+                    return Ok(roots);
+                }
+            }
+            return Ok(vec![self.visit(
+                &Node::Factors(vec![
+                    (TokenType::Mul, Node::Num(-1f64)),
+                    (TokenType::Mul, intercept),
+                    (TokenType::Div, slope),
+                ]),
+                None,
+            )?]);
+        }
+        let poly = self.polynomial(&f, var);
+        if let Some(poly) = poly.as_ref().filter(|poly| poly.degree() == 0) {
+            return Err(InterpreterError::SolveError(String::from(
+                if poly.coefficients().is_empty() {
+                    "Infinitely many solutions"
+                } else {
+                    "No solutions"
+                },
+            )));
+        }
+        if let Some(mut roots) = poly.and_then(|poly| self.solve_polynomial(&poly)) {
+            let mut values = InterpreterResult::<Vec<(f64, Node)>>::from_iter(
+                roots
+                    .drain(..)
+                    .map(|root| Ok((self.evaluate(&root, &HashMap::new())?, root))),
+            )?;
+            values.sort_by(|a, b| a.0.total_cmp(&b.0));
+            values.dedup_by(|a, b| (a.0 - b.0).abs() <= 1e-9 * a.0.abs().max(1f64));
+            return Ok(values.into_iter().map(|(_, root)| root).collect());
+        }
+        Ok(vec![Node::Num(self.solve_equation(eq, var, 0f64)?)])
+    }
+
+    pub fn solve(&self, eq: &Node) -> InterpreterResult<Node> {
+        let unknowns = self.unknowns(eq);
+        let var = if unknowns.contains(&'x') {
+            'x'
+        } else if let [var] = unknowns[..] {
+            var
+        } else {
+            return Err(InterpreterError::SolveError(String::from(
+                "Cannot determine the unknown",
+            )));
+        };
+        let mut solutions = self
+            .solve_for(eq, var)?
+            .into_iter()
+            .map(|solution| Node::Equation {
+                lhs: Box::new(Node::Var(var)),
+                rhs: Box::new(solution),
+            })
+            .collect::<Vec<_>>();
+        match solutions.len() {
+            0 => Err(InterpreterError::SolveError(String::from(
+                "No real solutions",
+            ))),
+            1 => Ok(solutions.remove(0)),
+            _ => Ok(Node::Vector(solutions)),
+        }
+    }
+
+    pub fn solve_equations(&self, eqs: &[Node]) -> InterpreterResult<Node> {
+        let mut vars = vec![];
+        for eq in eqs {
+            vars.extend(self.unknowns(eq));
+        }
+        vars.sort_unstable();
+        vars.dedup();
+        if let Some(solution) = self.solve_linear(eqs, &vars)? {
+            return Ok(solution);
+        }
+        let solution =
+            self.solve_system(eqs, &vars, &vec![1f64; vars.len()], SystemMethod::Newton)?;
+        Ok(Node::System(
+            vars.iter()
+                .zip(solution)
+                .map(|(var, val)| Node::Equation {
+                    lhs: Box::new(Node::Var(*var)),
+                    rhs: Box::new(Node::Num(val)),
+                })
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cubic_roots;
+    use crate::{interpreter::run, node::Node};
+
+    fn sorted(mut roots: Vec<f64>) -> Vec<f64> {
+        roots.sort_by(f64::total_cmp);
+        roots
+    }
+
+    fn assert_roots(roots: Vec<f64>, expected: &[f64]) {
+        let roots = sorted(roots);
+        assert_eq!(roots.len(), expected.len(), "{roots:?}");
+        for (root, expected) in roots.iter().zip(expected) {
+            assert!((root - expected).abs() < 1e-9, "{roots:?}");
+        }
+    }
+
+    #[test]
+    fn cubic_distinct_roots() {
+        assert_roots(cubic_roots(1f64, -6f64, 11f64, -6f64), &[1f64, 2f64, 3f64]);
+    }
+
+    #[test]
+    fn cubic_double_root() {
+        let sqrt2 = 2f64.sqrt();
+        assert_roots(
+            cubic_roots(1f64, 0f64, -6f64, 4f64 * sqrt2),
+            &[-2f64 * sqrt2, sqrt2],
+        );
+    }
+
+    #[test]
+    fn cubic_triple_root() {
+        assert_roots(cubic_roots(1f64, -3f64, 3f64, -1f64), &[1f64]);
+    }
+
+    #[test]
+    fn cubic_single_real_root() {
+        assert_roots(cubic_roots(1f64, 0f64, 1f64, -2f64), &[1f64]);
+    }
+
+    #[test]
+    fn irrational_cubic_roots_are_estimates() {
+        let Ok(Node::Equation { rhs, .. }) = run("x^3-2=0") else {
+            panic!()
+        };
+        let Node::Estimate { value, error } = *rhs else {
+            panic!("{rhs}")
+        };
+        assert!((value - 2f64.cbrt()).abs() <= error);
+        assert!(error < 1e-12);
+        assert_eq!(
+            run("x^3-6x^2+11x-6=0").unwrap().to_string(),
+            "[x = 1, x = 2, x = 3]"
+        );
+    }
+
+    #[test]
+    fn solves_quadratic_equation() {
+        assert_eq!(run("x^2-5x+6=0").unwrap().to_string(), "[x = 2, x = 3]");
+    }
+}
@@ -0,0 +1,134 @@
+use std::cell::{Cell, RefCell};
+
+use crate::{node::Node, token::TokenType};
+
+use super::Interpreter;
+
+#[derive(Debug, Default)]
+pub struct Trace {
+    lines: RefCell<Vec<(usize, String)>>,
+    depth: Cell<usize>,
+}
+
+impl Interpreter {
+    pub fn take_steps(&self) -> Vec<String> {
+        let Some(trace) = &self.trace else {
+            return vec![];
+        };
+        trace.depth.set(0);
+        trace
+            .lines
+            .take()
+            .into_iter()
+            .map(|(depth, line)| format!("{}{line}", "  ".repeat(depth.saturating_sub(1))))
+            .collect()
+    }
+
+    pub fn tracing(&self) -> bool {
+        self.trace
+            .as_ref()
+            .is_some_and(|trace| trace.depth.get() > 0)
+    }
+
+    pub fn enter_steps(&self) {
+        if let Some(trace) = &self.trace {
+            trace.depth.set(trace.depth.get() + 1);
+        }
+    }
+
+    pub fn leave_steps(&self) {
+        if let Some(trace) = &self.trace {
+            trace.depth.set(trace.depth.get().saturating_sub(1));
+        }
+    }
+
+    pub fn note_step<F: FnOnce() -> String>(&self, line: F) {
+        if let Some(trace) = &self.trace {
+            trace.lines.borrow_mut().push((trace.depth.get(), line()));
+        }
+    }
+
+    pub fn open_step(&self) -> Option<usize> {
+        let trace = self.trace.as_ref()?;
+        let mut lines = trace.lines.borrow_mut();
+        lines.push((trace.depth.get(), String::new()));
+        trace.depth.set(trace.depth.get() + 1);
+        Some(lines.len() - 1)
+    }
+
+    pub fn close_step(&self, index: Option<usize>, line: Option<String>) {
+        let (Some(trace), Some(index)) = (&self.trace, index) else {
+            return;
+        };
+        trace.depth.set(trace.depth.get() - 1);
+        let mut lines = trace.lines.borrow_mut();
+        match line {
+            Some(line) => lines[index].1 = line,
+            None => lines.truncate(index),
+        }
+    }
+
+    pub fn differentiation_rule(&self, node: &Node, var: char) -> String {
+        let depends = |node: &Node| self.unknowns(node).contains(&var);
+        let chain = |inner: &Node, rule: &str| match inner {
+            Node::Var(v) if *v == var => String::from(rule),
+            _ => format!("chain rule, {rule}"),
+        };
+        if !depends(node) {
+            return String::from("constant rule");
+        }
+        match node {
+            Node::Func { func, arg } => chain(arg, &format!("{func} rule")),
+            Node::Exponent { base, exponent } if !depends(exponent) => chain(base, "power rule"),
+            Node::Exponent { base, exponent } if !depends(base) => {
+                chain(exponent, "exponential rule")
+            }
+            Node::Exponent { .. } => String::from("logarithmic differentiation"),
+            Node::Factors(factors) => {
+                let dependent = factors
+                    .iter()
+                    .filter(|(_, factor)| depends(factor))
+                    .collect::<Vec<_>>();
+                if dependent.iter().any(|(op, _)| *op == TokenType::Div) {
+                    String::from("quotient rule")
+                } else if dependent.len() > 1 {
+                    String::from("product rule")
+                } else {
+                    String::from("constant multiple rule")
+                }
+            }
+            Node::Terms(_) => String::from("sum rule"),
+            Node::Derivative { .. } | Node::Equation { .. } => {
+                String::from("implicit differentiation")
+            }
+            _ => String::from("evaluation"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::{parse, Interpreter};
+
+    #[test]
+    fn chain_rule_steps() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_steps(true);
+        interpreter.visit(&parse("d/dx(sin(x^2))"), None).unwrap();
+        assert_eq!(
+            interpreter.take_steps(),
+            [
+                "d/dx[sin(x^2)] = 2*x*cos(x^2)  (chain rule, sin rule)",
+                "  d/dx[x^2] = 2*x  (power rule)",
+            ]
+        );
+        assert!(interpreter.take_steps().is_empty());
+    }
+
+    #[test]
+    fn no_steps_when_disabled() {
+        let interpreter = Interpreter::new();
+        interpreter.visit(&parse("d/dx(sin(x^2))"), None).unwrap();
+        assert!(interpreter.take_steps().is_empty());
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+
+use crate::{
+    node::Node,
+    token::{CommandType, FuncType, TokenType},
+};
+
+use super::{estimate, polynomial::Polynomial, Interpreter, InterpreterError, InterpreterResult};
+
+const MAX_TERMS: f64 = 1e6;
+const LEVELS: usize = 16;
+
+fn diverges() -> InterpreterError {
+    InterpreterError::SolveError(String::from("Series diverges"))
+}
+
+fn count(a: f64, b: &Node) -> Node {
+    Node::Terms(vec![
+        (TokenType::Plus, b.clone()),
+        (TokenType::Plus, Node::Num(1f64 - a)),
+    ])
+}
+
+fn shanks(sums: &[f64]) -> (f64, f64) {
+    let mut sums = sums.to_vec();
+    while sums.len() >= 3 {
+        let next = sums
+            .windows(3)
+            .map(|w| {
+                let denominator = w[2] - 2f64 * w[1] + w[0];
+                if denominator == 0f64 {
+                    w[2]
+                } else {
+                    w[2] - (w[2] - w[1]).powi(2) / denominator
+                }
+            })
+            .collect::<Vec<_>>();
+        if next.iter().any(|s| !s.is_finite()) {
+            break;
+        }
+        sums = next;
+    }
+    match sums[..] {
+        [.., a, b] => (b, (b - a).abs()),
+        [a] => (a, f64::INFINITY),
+        [] => (f64::NAN, f64::INFINITY),
+    }
+}
+
+fn richardson(sums: &[f64], exponent: f64) -> (f64, f64) {
+    let mut previous: Vec<f64> = vec![];
+    let (mut best, mut error) = (f64::NAN, f64::INFINITY);
+    for (i, sum) in sums.iter().enumerate() {
+        let mut row = vec![*sum];
+        for j in 1..=i {
+            let factor = 2f64.powf(exponent + (j - 1) as f64);
+            let extrapolated = row[j - 1] + (row[j - 1] - previous[j - 1]) / (factor - 1f64);
+            let estimate = (extrapolated - row[j - 1])
+                .abs()
+                .max((extrapolated - previous[j - 1]).abs());
+            row.push(extrapolated);
+            if estimate <= error {
+                (best, error) = (extrapolated, estimate);
+            }
+        }
+        previous = row;
+    }
+    (best, error)
+}
+
+impl Interpreter {
+    fn term_at(&self, f: &Node, var: char, k: f64) -> InterpreterResult<f64> {
+        self.evaluate(f, &HashMap::from([(var, k)]))
+    }
+
+    fn constant_ratio_at(&self, f: &Node, var: char, a: f64) -> Option<f64> {
+        let terms = (0..6)
+            .map(|i| self.term_at(f, var, a + i as f64).ok())
+            .collect::<Option<Vec<_>>>()?;
+        let ratios = terms.windows(2).map(|w| w[1] / w[0]).collect::<Vec<_>>();
+        (ratios[0].is_finite()
+            && ratios
+                .iter()
+                .all(|r| (r - ratios[0]).abs() <= 1e-12 * ratios[0].abs().max(1f64)))
+        .then_some(ratios[0])
+    }
+
+    fn faulhaber(&self, poly: &Polynomial, a: f64) -> Polynomial {
+        let degree = poly.degree() + 1;
+        let mut differences = (0..=degree)
+            .scan(0f64, |sum, m| {
+                let partial = *sum;
+                *sum += poly.eval(a + m as f64);
+                Some(partial)
+            })
+            .collect::<Vec<_>>();
+        let mut result = Polynomial::new(vec![]);
+        let mut basis = Polynomial::constant(1f64);
+        for j in 0..=degree {
+            result = &result + &basis.scale(differences[0]);
+            basis = &basis.scale(1f64 / (j + 1) as f64)
+                * &Polynomial::new(vec![1f64 - a - j as f64, 1f64]);
+            differences = differences.windows(2).map(|w| w[1] - w[0]).collect();
+        }
+        result
+    }
+
+    fn closed_form_sum(&self, f: &Node, var: char, a: f64, b: &Node) -> Option<Node> {
+        if let Some(poly) = self.polynomial(f, var) {
+            return Some(self.faulhaber(&poly, a).to_node_at(b));
+        }
+        if let Node::Terms(terms) = f {
+            return Some(Node::Terms(
+                terms
+                    .iter()
+                    .map(|(op, term)| Some((*op, self.closed_form_sum(term, var, a, b)?)))
+                    .collect::<Option<Vec<_>>>()?,
+            ));
+        }
+        let r = self.constant_ratio_at(f, var, a)?;
+        let first = self.term_at(f, var, a).ok()?;
+        Some(Node::Factors(vec![
+            (TokenType::Mul, Node::Num(first / (r - 1f64))),
+            (
+                TokenType::Mul,
+                Node::Terms(vec![
+                    (
+                        TokenType::Plus,
+                        Node::Exponent {
+                            base: Box::new(Node::Num(r)),
+                            exponent: Box::new(count(a, b)),
+                        },
+                    ),
+                    (TokenType::Minus, Node::Num(1f64)),
+                ]),
+            ),
+        ]))
+    }
+
+    fn closed_form_product(&self, f: &Node, var: char, a: f64, b: &Node) -> Option<Node> {
+        if !self.unknowns(f).contains(&var) {
+            return Some(Node::Exponent {
+                base: Box::new(f.clone()),
+                exponent: Box::new(count(a, b)),
+            });
+        }
+        match f {
+            Node::Exponent { base, exponent } if !self.unknowns(base).contains(&var) => {
+                Some(Node::Exponent {
+                    base: base.clone(),
+                    exponent: Box::new(self.closed_form_sum(exponent, var, a, b)?),
+                })
+            }
+            Node::Factors(factors) => Some(Node::Factors(
+                factors
+                    .iter()
+                    .map(|(op, factor)| Some((*op, self.closed_form_product(factor, var, a, b)?)))
+                    .collect::<Option<Vec<_>>>()?,
+            )),
+            _ => None,
+        }
+    }
+
+    fn infinite_sum(&self, f: &Node, var: char, a: f64) -> InterpreterResult<(f64, f64)> {
+        if let Some(poly) = self.polynomial(f, var) {
+            return match poly.coefficients() {
+                [] => Ok((0f64, 0f64)),
+                _ => Err(diverges()),
+            };
+        }
+        if let Node::Terms(terms) = f {
+            return terms
+                .iter()
+                .try_fold((0f64, 0f64), |(sum, error), (op, term)| {
+                    let (value, estimate) = self.infinite_sum(term, var, a)?;
+                    Ok(match op {
+                        TokenType::Minus => (sum - value, error + estimate),
+                        _ => (sum + value, error + estimate),
+                    })
+                });
+        }
+        if let Some(r) = self.constant_ratio_at(f, var, a) {
+            return if r.abs() < 1f64 {
+                Ok((self.term_at(f, var, a)? / (1f64 - r), 0f64))
+            } else {
+                Err(diverges())
+            };
+        }
+        let infinity = |node: &Node| {
+            self.limit(
+                node,
+                &Node::Approach {
+                    var,
+                    target: Box::new(Node::Num(f64::INFINITY)),
+                    side: None,
+                },
+            )
+        };
+        match infinity(f) {
+            Ok(Node::Num(0f64)) => {}
+            _ => return Err(diverges()),
+        }
+        let next = f.substitute(
+            var,
+            &Node::Terms(vec![
+                (TokenType::Plus, Node::Var(var)),
+                (TokenType::Plus, Node::Num(1f64)),
+            ]),
+        );
+        let ratio = match infinity(&Node::Factors(vec![
+            (TokenType::Mul, next),
+            (TokenType::Div, f.clone()),
+        ])) {
+            Ok(Node::Num(ratio)) => Some(ratio.abs()),
+            _ => {
+                let ratio_at = |k: f64| -> InterpreterResult<f64> {
+                    Ok((self.term_at(f, var, k + 1f64)? / self.term_at(f, var, k)?).abs())
+                };
+                let (near, far) = (ratio_at(a + 100f64)?, ratio_at(a + 200f64)?);
+                (far < 0.95 && (near - far).abs() < 0.01).then_some(far)
+            }
+        };
+        if ratio.is_some_and(|ratio| ratio > 1f64 + 1e-9) {
+            return Err(diverges());
+        }
+        if let Some(ratio) = ratio.filter(|ratio| *ratio < 1f64 - 1e-9) {
+            let (mut sum, mut k) = (0f64, a);
+            loop {
+                let term = self.term_at(f, var, k)?;
+                sum += term;
+                k += 1f64;
+                if term.abs() <= 1e-17 * sum.abs() || k - a > MAX_TERMS {
+                    return Ok((sum, term.abs() * ratio / (1f64 - ratio)));
+                }
+            }
+        }
+        let terms = InterpreterResult::<Vec<f64>>::from_iter(
+            (0..1usize << LEVELS).map(|i| self.term_at(f, var, a + i as f64)),
+        )?;
+        let sums = terms
+            .iter()
+            .scan(0f64, |sum, term| {
+                *sum += term;
+                Some(*sum)
+            })
+            .collect::<Vec<_>>();
+        if terms[..64].windows(2).all(|w| w[0] * w[1] < 0f64) {
+            return Ok(shanks(&sums[..40]));
+        }
+        let [smallest, small, large] =
+            [LEVELS - 2, LEVELS - 1, LEVELS].map(|level| terms[(1 << level) - 1]);
+        if large == 0f64 {
+            return Ok((sums[sums.len() - 1], small.abs()));
+        }
+        let decay = |from: f64, to: f64| -(to / from).abs().log2() - 1f64;
+        let (near, far) = (decay(smallest, small), decay(small, large));
+        let drift = (near - far) * (LEVELS - 1) as f64;
+        if far - drift <= 0.02 {
+            return Err(diverges());
+        }
+        let exponent = match far {
+            exponent if (2f64 * exponent - (2f64 * exponent).round()).abs() < 1e-3 => {
+                (2f64 * exponent).round() / 2f64
+            }
+            exponent => exponent,
+        };
+        Ok(richardson(
+            &(4..=LEVELS)
+                .map(|level| sums[(1 << level) - 1])
+                .collect::<Vec<_>>(),
+            exponent,
+        ))
+    }
+
+    pub fn summation(&self, command: CommandType, args: &[Node]) -> InterpreterResult<Node> {
+        let invalid = || InterpreterError::InvalidArguments(command);
+        let [f, var, a, b] = args else {
+            return Err(invalid());
+        };
+        let f = self.visit(f, None)?;
+        let var = var.as_var().ok_or_else(invalid)?;
+        let a = self.evaluate(a, &HashMap::new())?;
+        if a.fract() != 0f64 {
+            return Err(invalid());
+        }
+        let b = self.visit(b, None)?;
+        let product = command == CommandType::Prod;
+        match b {
+            Node::Num(b) if b == f64::INFINITY => {
+                if !product {
+                    let (value, error) = self.infinite_sum(&f, var, a)?;
+                    return Ok(estimate(value, error));
+                }
+                let ln = Node::Func {
+                    func: FuncType::Ln,
+                    arg: Box::new(f),
+                };
+                let (value, error) = self.infinite_sum(&ln, var, a)?;
+                Ok(estimate(value.exp(), value.exp() * error))
+            }
+            Node::Num(b) if b.fract() != 0f64 => Err(invalid()),
+            Node::Num(b) if b - a < MAX_TERMS => {
+                let mut k = a;
+                let mut value = if product { 1f64 } else { 0f64 };
+                while k <= b {
+                    let term = self.term_at(&f, var, k)?;
+                    if product {
+                        value *= term;
+                    } else {
+                        value += term;
+                    }
+                    k += 1f64;
+                }
+                Ok(Node::Num(value))
+            }
+            b => {
+                let closed_form = if product {
+                    self.closed_form_product(&f, var, a, &b)
+                } else {
+                    self.closed_form_sum(&f, var, a, &b)
+                };
+                let closed_form = closed_form
+                    .ok_or_else(|| InterpreterError::SolveError(String::from("No closed form")))?;
+                Ok(self.simplify(&self.visit(&closed_form, None)?))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interpreter::{run, InterpreterError},
+        node::Node,
+    };
+
+    fn value(line: &str) -> f64 {
+        run(line).unwrap().as_estimate().unwrap().0
+    }
+
+    #[test]
+    fn basel_problem() {
+        let sum = value("sum(1/k^2, k, 1, ∞)");
+        assert!((sum - std::f64::consts::PI.powi(2) / 6f64).abs() < 1e-12);
+    }
+
+    #[test]
+    fn alternating_harmonic_series() {
+        let sum = value("sum((-1)^(k+1)/k, k, 1, ∞)");
+        assert!((sum - std::f64::consts::LN_2).abs() < 1e-12);
+    }
+
+    #[test]
+    fn geometric_series() {
+        assert_eq!(value("sum(0.5^k, k, 0, ∞)"), 2f64);
+    }
+
+    #[test]
+    fn harmonic_series_diverges() {
+        assert!(matches!(
+            run("sum(1/k, k, 1, ∞)"),
+            Err(InterpreterError::SolveError(_))
+        ));
+    }
+
+    #[test]
+    fn finite_sums_and_products() {
+        assert_eq!(run("prod(k, k, 1, 5)").unwrap(), Node::Num(120f64));
+        assert_eq!(run("sum(k^2, k, 1, 10)").unwrap(), Node::Num(385f64));
+        assert_eq!(run("sum(i^2, i, 1, 3)").unwrap(), Node::Num(14f64));
+    }
+
+    #[test]
+    fn closed_form_in_the_upper_bound() {
+        for line in ["sum(k, k, 1, n)|n=10", "sum(k, k, 1, k)|k=10"] {
+            assert!((value(line) - 55f64).abs() < 1e-9, "{line}");
+        }
+        assert!((value("sum(2^k, k, 0, n)|n=10") - 2047f64).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,504 @@
+use std::collections::HashMap;
+
+use crate::{
+    node::Node,
+    token::{FuncType, TokenType},
+};
+
+use super::{
+    ode::{derivative_order, derivatives},
+    Interpreter, InterpreterError, InterpreterResult,
+};
+
+fn placeholder(order: u32) -> char {
+    char::from_u32(0xE100 + order).expect("private use characters are valid")
+}
+
+fn clean(n: f64) -> f64 {
+    if (n - n.round()).abs() < 1e-9 {
+        n.round() + 0f64
+    } else {
+        n
+    }
+}
+
+fn exp(exponent: Node) -> Node {
+    Node::Exponent {
+        base: Box::new(Node::Var('e')),
+        exponent: Box::new(exponent),
+    }
+}
+
+fn linear_exp(rate: f64, var: char) -> Node {
+    exp(Node::Factors(vec![
+        (TokenType::Mul, Node::Num(clean(rate))),
+        (TokenType::Mul, Node::Var(var)),
+    ]))
+}
+
+fn product(factors: &[Node]) -> Node {
+    Node::Factors(
+        factors
+            .iter()
+            .map(|factor| (TokenType::Mul, factor.clone()))
+            .collect(),
+    )
+}
+
+fn negate(node: Node) -> Node {
+    product(&[Node::Num(-1f64), node])
+}
+
+fn sum(terms: &[Node]) -> Node {
+    Node::Terms(
+        terms
+            .iter()
+            .map(|term| (TokenType::Plus, term.clone()))
+            .collect(),
+    )
+}
+
+fn flatten(node: &Node) -> Vec<(TokenType, Node)> {
+    match node {
+        Node::Factors(factors) => factors
+            .iter()
+            .flat_map(|(op, factor)| {
+                flatten(factor)
+                    .into_iter()
+                    .map(move |(inner, factor)| match (op, inner) {
+                        (TokenType::Div, TokenType::Mul) => (TokenType::Div, factor),
+                        (TokenType::Div, _) => (TokenType::Mul, factor),
+                        (_, inner) => (inner, factor),
+                    })
+            })
+            .collect(),
+        Node::Terms(terms) => match &terms[..] {
+            [(TokenType::Plus, term)] => flatten(term),
+            [(TokenType::Minus, term)] => {
+                let mut factors = vec![(TokenType::Mul, Node::Num(-1f64))];
+                factors.extend(flatten(term));
+                factors
+            }
+            _ => vec![(TokenType::Mul, node.clone())],
+        },
+        _ => vec![(TokenType::Mul, node.clone())],
+    }
+}
+
+impl Interpreter {
+    fn isolate(&self, mut lhs: Node, mut rhs: Node, var: char) -> Option<Node> {
+        loop {
+            let depends = |node: &Node| self.unknowns(node).contains(&var);
+            (lhs, rhs) = match lhs {
+                Node::Var(v) if v == var => return self.visit(&rhs, None).ok(),
+                Node::Terms(terms) => {
+                    let [(op, term)] = &terms
+                        .iter()
+                        .filter(|(_, term)| depends(term))
+                        .collect::<Vec<_>>()[..]
+                    else {
+                        return None;
+                    };
+                    let mut rest = vec![(TokenType::Plus, rhs)];
+                    rest.extend(terms.iter().filter(|(_, t)| !depends(t)).map(
+                        |(op, t)| match op {
+                            TokenType::Plus => (TokenType::Minus, t.clone()),
+                            _ => (TokenType::Plus, t.clone()),
+                        },
+                    ));
+                    let rest = Node::Terms(rest);
+                    let rhs = match op {
+                        TokenType::Minus => negate(rest),
+                        _ => rest,
+                    };
+                    (term.clone(), rhs)
+                }
+                Node::Factors(factors) => {
+                    let [(op, factor)] = &factors
+                        .iter()
+                        .filter(|(_, factor)| depends(factor))
+                        .collect::<Vec<_>>()[..]
+                    else {
+                        return None;
+                    };
+                    let mut rest = vec![(TokenType::Mul, rhs)];
+                    rest.extend(factors.iter().filter(|(_, f)| !depends(f)).map(
+                        |(op, f)| match op {
+                            TokenType::Mul => (TokenType::Div, f.clone()),
+                            _ => (TokenType::Mul, f.clone()),
+                        },
+                    ));
+                    let rest = Node::Factors(rest);
+                    let rhs = match op {
+                        TokenType::Div => Node::Factors(vec![(TokenType::Div, rest)]),
+                        _ => rest,
+                    };
+                    (factor.clone(), rhs)
+                }
+                Node::Exponent { base, exponent } if !depends(&exponent) => (
+                    *base,
+                    Node::Exponent {
+                        base: Box::new(rhs),
+                        exponent: Box::new(Node::Factors(vec![(TokenType::Div, *exponent)])),
+                    },
+                ),
+                Node::Exponent { base, exponent } if !depends(&base) => (
+                    *exponent,
+                    Node::Factors(vec![
+                        (
+                            TokenType::Mul,
+                            Node::Func {
+                                func: FuncType::Ln,
+                                arg: Box::new(rhs),
+                            },
+                        ),
+                        (
+                            TokenType::Div,
+                            Node::Func {
+                                func: FuncType::Ln,
+                                arg: base,
+                            },
+                        ),
+                    ]),
+                ),
+                Node::Func { func, arg } => (
+                    *arg,
+                    match func {
+                        FuncType::Ln => exp(rhs),
+                        FuncType::Log => Node::Exponent {
+                            base: Box::new(Node::Num(10f64)),
+                            exponent: Box::new(rhs),
+                        },
+                        FuncType::Sqrt => Node::Exponent {
+                            base: Box::new(rhs),
+                            exponent: Box::new(Node::Num(2f64)),
+                        },
+                        _ => return None,
+                    },
+                ),
+                _ => return None,
+            };
+        }
+    }
+
+    fn separable(&self, f: &Node, var: char, unknown: char) -> Option<(Node, Node)> {
+        let (mut g, mut h) = (vec![], vec![]);
+        for (op, factor) in flatten(f) {
+            let unknowns = self.unknowns(&factor);
+            match (unknowns.contains(&var), unknowns.contains(&unknown)) {
+                (true, true) => return None,
+                (false, true) => h.push((op, factor)),
+                _ => g.push((op, factor)),
+            }
+        }
+        (!h.is_empty()).then(|| {
+            (
+                Node::Factors(g),
+                Node::Factors(
+                    h.into_iter()
+                        .map(|(op, factor)| match op {
+                            TokenType::Div => (TokenType::Mul, factor),
+                            _ => (TokenType::Div, factor),
+                        })
+                        .collect(),
+                ),
+            )
+        })
+    }
+
+    fn first_order(
+        &self,
+        f: &Node,
+        var: char,
+        unknown: char,
+        constant: char,
+    ) -> InterpreterResult<Node> {
+        let solution = |rhs| Node::Equation {
+            lhs: Box::new(Node::Var(unknown)),
+            rhs: Box::new(rhs),
+        };
+        let a = self.visit(&self.differentiate(f, unknown, None)?, None)?;
+        if !self.unknowns(&a).contains(&unknown) {
+            let b = self.visit(&f.substitute(unknown, &Node::Num(0f64)), None)?;
+            let growth = self.integrate(&a, var)?;
+            if b == Node::Num(0f64) {
+                return Ok(solution(product(&[Node::Var(constant), exp(growth)])));
+            }
+            let forced = self.integrate(&product(&[b, exp(negate(growth.clone()))]), var)?;
+            return Ok(solution(product(&[
+                exp(growth),
+                sum(&[forced, Node::Var(constant)]),
+            ])));
+        }
+        let Some((g, h)) = self.separable(f, var, unknown) else {
+            return Err(InterpreterError::SolveError(String::from(
+                "Differential equation is neither linear nor separable",
+            )));
+        };
+        Ok(Node::Equation {
+            lhs: Box::new(self.integrate(&h, unknown)?),
+            rhs: Box::new(sum(&[self.integrate(&g, var)?, Node::Var(constant)])),
+        })
+    }
+
+    fn second_order(
+        &self,
+        f: &Node,
+        var: char,
+        unknown: char,
+        constants: [char; 2],
+    ) -> InterpreterResult<Node> {
+        let slope = placeholder(1);
+        let coefficient = |v| match self.visit(&self.differentiate(f, v, None)?, None)? {
+            Node::Num(num) => Ok(num),
+            _ => Err(InterpreterError::SolveError(String::from(
+                "Only constant-coefficient linear second-order equations are supported",
+            ))),
+        };
+        let (a1, a0) = (coefficient(slope)?, coefficient(unknown)?);
+        let forcing = self.visit(
+            &f.substitute(unknown, &Node::Num(0f64))
+                .substitute(slope, &Node::Num(0f64)),
+            None,
+        )?;
+        let x = Node::Var(var);
+        let disc = a1 * a1 + 4f64 * a0;
+        let (basis, weights, scale) = if disc.abs() <= 1e-12 * a1.abs().max(1f64) {
+            let r = a1 / 2f64;
+            (
+                [
+                    linear_exp(r, var),
+                    product(&[x.clone(), linear_exp(r, var)]),
+                ],
+                [
+                    negate(product(&[x.clone(), linear_exp(-r, var)])),
+                    linear_exp(-r, var),
+                ],
+                1f64,
+            )
+        } else if disc > 0f64 {
+            let (r1, r2) = ((a1 - disc.sqrt()) / 2f64, (a1 + disc.sqrt()) / 2f64);
+            (
+                [linear_exp(r1, var), linear_exp(r2, var)],
+                [linear_exp(-r1, var), negate(linear_exp(-r2, var))],
+                r1 - r2,
+            )
+        } else {
+            let (alpha, beta) = (a1 / 2f64, (-disc).sqrt() / 2f64);
+            let trig = |func| Node::Func {
+                func,
+                arg: Box::new(product(&[Node::Num(clean(beta)), x.clone()])),
+            };
+            (
+                [
+                    product(&[linear_exp(alpha, var), trig(FuncType::Cos)]),
+                    product(&[linear_exp(alpha, var), trig(FuncType::Sin)]),
+                ],
+                [
+                    negate(product(&[linear_exp(-alpha, var), trig(FuncType::Sin)])),
+                    product(&[linear_exp(-alpha, var), trig(FuncType::Cos)]),
+                ],
+                beta,
+            )
+        };
+        let mut terms = vec![
+            product(&[Node::Var(constants[0]), basis[0].clone()]),
+            product(&[Node::Var(constants[1]), basis[1].clone()]),
+        ];
+        if forcing != Node::Num(0f64) {
+            for (y, weight) in basis.into_iter().zip(weights) {
+                let integral = self.integrate(&product(&[weight, forcing.clone()]), var)?;
+                terms.push(Node::Factors(vec![
+                    (TokenType::Mul, y),
+                    (TokenType::Mul, integral),
+                    (TokenType::Div, Node::Num(scale)),
+                ]));
+            }
+        }
+        Ok(Node::Equation {
+            lhs: Box::new(Node::Var(unknown)),
+            rhs: Box::new(sum(&terms)),
+        })
+    }
+
+    pub fn dsolve(&self, statements: &[Node]) -> InterpreterResult<Node> {
+        let error = |s: &str| InterpreterError::SolveError(String::from(s));
+        let mut equations = vec![];
+        let mut conditions = vec![];
+        for statement in statements {
+            let Node::Equation { lhs, rhs } = statement else {
+                return Err(error("Expected an equation"));
+            };
+            match self.condition(lhs, rhs) {
+                Some(condition) => conditions.push(condition),
+                None => equations.push(statement),
+            }
+        }
+        let [equation] = equations[..] else {
+            return Err(error(
+                "Only single differential equations can be solved symbolically",
+            ));
+        };
+        let found = derivatives(equation);
+        let (unknown, var, _) = found[0];
+        if found.iter().any(|(u, v, _)| (*u, *v) != (unknown, var)) {
+            return Err(error("Mixed dependent or independent variables"));
+        }
+        let order = found.iter().map(|(_, _, order)| *order).max().unwrap_or(1);
+        let f = self.move_equation(&equation.replace(&|node| {
+            derivative_order(node).map(|(_, _, order)| Node::Var(placeholder(order)))
+        }))?;
+        let highest = placeholder(order);
+        let f = match self.isolate(self.visit(&f, None)?, Node::Num(0f64), highest) {
+            Some(f) => f,
+            None => {
+                let coefficient = self.visit(&self.differentiate(&f, highest, None)?, None)?;
+                if self.unknowns(&coefficient).contains(&highest) || coefficient == Node::Num(0f64)
+                {
+                    return Err(error("Equation is not linear in its highest derivative"));
+                }
+                self.visit(
+                    &Node::Factors(vec![
+                        (TokenType::Mul, Node::Num(-1f64)),
+                        (TokenType::Mul, f.substitute(highest, &Node::Num(0f64))),
+                        (TokenType::Div, coefficient),
+                    ]),
+                    None,
+                )?
+            }
+        };
+        let mut vars = vec![];
+        statements
+            .iter()
+            .for_each(|statement| statement.collect_vars(&mut vars));
+        let constants = ('A'..='Z')
+            .filter(|c| !vars.contains(c))
+            .skip_while(|c| *c < 'C')
+            .take(order as usize)
+            .collect::<Vec<_>>();
+        let solution = match order {
+            1 => self.first_order(&f, var, unknown, constants[0])?,
+            2 => self.second_order(&f, var, unknown, [constants[0], constants[1]])?,
+            _ => {
+                return Err(error(
+                    "Only first- and second-order equations can be solved symbolically",
+                ))
+            }
+        };
+        let Node::Equation { lhs, rhs } = &solution else {
+            unreachable!()
+        };
+        let explicit = lhs.as_var() == Some(unknown);
+        let mut eqs = vec![];
+        for (dependent, k, at, value) in conditions {
+            if dependent != unknown || (!explicit && k > 0) {
+                return Err(error("Unsupported initial condition"));
+            }
+            let at = HashMap::from([(var, at), (unknown, value)]);
+            let mut f = if explicit {
+                *rhs.clone()
+            } else {
+                self.move_equation(&solution)?
+            };
+            for _ in 0..k {
+                f = self.visit(&self.differentiate(&f, var, None)?, None)?;
+            }
+            eqs.push(Node::Equation {
+                lhs: Box::new(self.visit(&f, Some(&at))?),
+                rhs: Box::new(Node::Num(if explicit { value } else { 0f64 })),
+            });
+        }
+        let values = if eqs.is_empty() {
+            vec![]
+        } else {
+            match self.solve_linear(&eqs, &constants)? {
+                Some(Node::System(values)) => values,
+                _ => return Err(error("Could not apply initial conditions")),
+            }
+        };
+        let values = values
+            .into_iter()
+            .filter_map(|value| match value {
+                Node::Equation { lhs, rhs } => Some((lhs.as_var()?, *rhs)),
+                _ => None,
+            })
+            .collect::<HashMap<_, _>>();
+        let apply = |node: &Node| {
+            self.visit(
+                &node.replace(&|node| node.as_var().and_then(|v| values.get(&v)).cloned()),
+                None,
+            )
+        };
+        let (lhs, rhs) = (apply(lhs)?, apply(rhs)?);
+        Ok(
+            self.simplify(&match self.isolate(lhs.clone(), rhs.clone(), unknown) {
+                Some(explicit) => Node::Equation {
+                    lhs: Box::new(Node::Var(unknown)),
+                    rhs: Box::new(explicit),
+                },
+                None => Node::Equation {
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                },
+            }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        interpreter::{run, Interpreter},
+        node::Node,
+    };
+
+    fn solution(line: &str) -> impl Fn(f64) -> f64 {
+        let Ok(Node::Equation { rhs, .. }) = run(line) else {
+            panic!("{line}")
+        };
+        move |x| {
+            Interpreter::new()
+                .evaluate(&rhs, &HashMap::from([('x', x), ('C', 1.3), ('D', -0.4)]))
+                .unwrap()
+        }
+    }
+
+    fn assert_satisfies(line: &str, residual: impl Fn(f64, f64, f64, f64) -> f64) {
+        let y = solution(line);
+        let h = 1e-4;
+        for x in [0.2, 0.9, 1.6] {
+            let slope = (y(x + h) - y(x - h)) / (2f64 * h);
+            let curvature = (y(x + h) - 2f64 * y(x) + y(x - h)) / (h * h);
+            assert!(residual(x, y(x), slope, curvature).abs() < 1e-5, "{line}");
+        }
+    }
+
+    #[test]
+    fn separable() {
+        assert_satisfies("dy/dx = x*y", |x, y, dy, _| dy - x * y);
+    }
+
+    #[test]
+    fn first_order_linear() {
+        assert_satisfies("dy/dx + y = x", |x, y, dy, _| dy + y - x);
+    }
+
+    #[test]
+    fn constant_coefficients() {
+        assert_satisfies("y''(x) - 3y' + 2y = 0", |_, y, dy, d2y| {
+            d2y - 3f64 * dy + 2f64 * y
+        });
+    }
+
+    #[test]
+    fn initial_conditions() {
+        let y = solution("y''(x) + y = 0, y(0) = 0, y'(0) = 1");
+        for x in [0.3, 1.1] {
+            assert!((y(x) - x.sin()).abs() < 1e-9);
+        }
+        let y = solution("y'(x) = y, y(0) = 3");
+        assert!((y(1f64) - 3f64 * std::f64::consts::E).abs() < 1e-9);
+    }
+}
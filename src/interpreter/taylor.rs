@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+
+use crate::{
+    node::Node,
+    token::{CommandType, FuncType, TokenType},
+};
+
+use super::{Interpreter, InterpreterError, InterpreterResult};
+
+const MAX_POLE: usize = 8;
+const LOOKAHEAD: usize = 4;
+
+fn monomial(var: char, a: f64, power: i32) -> Option<Node> {
+    let base = match a {
+        0f64 => Node::Var(var),
+        a if a < 0f64 => Node::Terms(vec![
+            (TokenType::Plus, Node::Var(var)),
+            (TokenType::Plus, Node::Num(-a)),
+        ]),
+        a => Node::Terms(vec![
+            (TokenType::Plus, Node::Var(var)),
+            (TokenType::Minus, Node::Num(a)),
+        ]),
+    };
+    match power.abs() {
+        0 => None,
+        1 => Some(base),
+        p => Some(Node::Exponent {
+            base: Box::new(base),
+            exponent: Box::new(Node::Num(p as f64)),
+        }),
+    }
+}
+
+fn term(c: f64, var: char, a: f64, power: i32) -> Node {
+    match monomial(var, a, power) {
+        None => Node::Num(c),
+        Some(m) if c == 1f64 && power > 0 => m,
+        Some(m) if power < 0 => {
+            Node::Factors(vec![(TokenType::Mul, Node::Num(c)), (TokenType::Div, m)])
+        }
+        Some(m) => Node::Factors(vec![(TokenType::Mul, Node::Num(c)), (TokenType::Mul, m)]),
+    }
+}
+
+fn quotient(num: Node, den: Node) -> Node {
+    Node::Factors(vec![(TokenType::Mul, num), (TokenType::Div, den)])
+}
+
+fn trig_quotients(node: &Node) -> Node {
+    node.replace(&|node| {
+        let Node::Func { func, arg } = node else {
+            return None;
+        };
+        let arg = trig_quotients(arg);
+        let apply = |func| Node::Func {
+            func,
+            arg: Box::new(arg.clone()),
+        };
+        Some(match func {
+            FuncType::Tan => quotient(apply(FuncType::Sin), apply(FuncType::Cos)),
+            FuncType::Cot => quotient(apply(FuncType::Cos), apply(FuncType::Sin)),
+            FuncType::Sec => quotient(Node::Num(1f64), apply(FuncType::Cos)),
+            FuncType::Csc => quotient(Node::Num(1f64), apply(FuncType::Sin)),
+            _ => apply(func.clone()),
+        })
+    })
+}
+
+fn split(node: &Node) -> Option<(Node, Node)> {
+    match node {
+        Node::Factors(factors) if factors.iter().any(|(op, _)| *op == TokenType::Div) => {
+            let (num, den): (Vec<_>, Vec<_>) = factors
+                .iter()
+                .cloned()
+                .partition(|(op, _)| *op != TokenType::Div);
+            let product = |nodes: Vec<(TokenType, Node)>| {
+                Node::Factors(
+                    nodes
+                        .into_iter()
+                        .map(|(_, node)| (TokenType::Mul, node))
+                        .chain(std::iter::once((TokenType::Mul, Node::Num(1f64))))
+                        .collect(),
+                )
+            };
+            Some((product(num), product(den)))
+        }
+        Node::Exponent { base, exponent } => match exponent.as_ref() {
+            Node::Num(n) if *n < 0f64 => Some((
+                Node::Num(1f64),
+                Node::Exponent {
+                    base: base.clone(),
+                    exponent: Box::new(Node::Num(-n)),
+                },
+            )),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+impl Interpreter {
+    fn series(
+        &self,
+        f: &Node,
+        var: char,
+        a: f64,
+        count: usize,
+    ) -> InterpreterResult<(i32, Vec<f64>)> {
+        if let Some((num, den)) = split(f) {
+            let (num_order, num) = self.series(&num, var, a, count)?;
+            let (den_order, den) = self.series(&den, var, a, count)?;
+            if den[0] == 0f64 {
+                return Err(InterpreterError::Undefined);
+            }
+            let mut coefficients: Vec<f64> = vec![];
+            for k in 0..count {
+                let known = (1..=k).map(|j| den[j] * coefficients[k - j]).sum::<f64>();
+                coefficients.push((num[k] - known) / den[0]);
+            }
+            return Ok((num_order - den_order, coefficients));
+        }
+        let at = HashMap::from([(var, a)]);
+        let mut g = f.clone();
+        let mut factorial = 1f64;
+        let mut scale = 1f64;
+        let mut order = None;
+        let mut coefficients = vec![];
+        for k in 0..count + MAX_POLE {
+            if k > 0 {
+                g = self.simplify(&self.differentiate(&g, var, None)?);
+                factorial *= k as f64;
+            }
+            let value = match self.evaluate(&g, &at) {
+                Ok(value) if value.is_finite() => value,
+                Ok(_)
+                | Err(
+                    InterpreterError::Undefined
+                    | InterpreterError::Infinity
+                    | InterpreterError::NegInfinity,
+                ) => {
+                    return Err(InterpreterError::SolveError(String::from(
+                        "Singularity is not a pole",
+                    )))
+                }
+                Err(err) => return Err(err),
+            };
+            let coefficient = value / factorial;
+            scale = scale.max(coefficient.abs());
+            if order.is_none() && coefficient.abs() > 1e-12 * scale {
+                order = Some(k as i32);
+            }
+            if order.is_some() {
+                coefficients.push(coefficient);
+                if coefficients.len() == count {
+                    break;
+                }
+            }
+        }
+        Ok(match order {
+            Some(order) => (order, coefficients),
+            None => (0, vec![0f64; count]),
+        })
+    }
+
+    pub fn taylor(&self, args: &[Node]) -> InterpreterResult<Node> {
+        let invalid = || InterpreterError::InvalidArguments(CommandType::Taylor);
+        let (expr, var, a, n) = match args {
+            [expr, var, n] => (expr, var, 0f64, n),
+            [expr, var, a, n] => (expr, var, self.evaluate(a, &HashMap::new())?, n),
+            _ => return Err(invalid()),
+        };
+        let var = var.as_var().ok_or_else(invalid)?;
+        let n = self.evaluate(n, &HashMap::new())?;
+        if n < 0f64 || n.fract() != 0f64 {
+            return Err(invalid());
+        }
+        let n = n as i32;
+        let f = trig_quotients(&self.visit(expr, None)?);
+        let (order, _) = self.series(&f, var, a, 1)?;
+        let count = (n - order + 1).max(0) as usize + LOOKAHEAD;
+        let (order, coefficients) = self.series(&f, var, a, count)?;
+        let scale = coefficients.iter().map(|c| c.abs()).fold(0f64, f64::max);
+        let (series, rest): (Vec<_>, Vec<_>) = coefficients
+            .into_iter()
+            .enumerate()
+            .map(|(k, c)| (order + k as i32, c))
+            .filter(|(_, c)| c.abs() > 1e-12 * scale)
+            .partition(|(power, _)| *power <= n);
+        let series = match &series[..] {
+            [] => Node::Num(0f64),
+            _ => Node::Terms(
+                series
+                    .into_iter()
+                    .map(|(power, c)| {
+                        let op = if c < 0f64 {
+                            TokenType::Minus
+                        } else {
+                            TokenType::Plus
+                        };
+                        (op, self.simplify(&term(c.abs(), var, a, power)))
+                    })
+                    .collect(),
+            ),
+        };
+        Ok(match rest.first() {
+            Some((power, c)) => Node::System(vec![
+                series,
+                Node::Equation {
+                    lhs: Box::new(Node::Var('R')),
+                    rhs: Box::new(self.simplify(&term(*c, var, a, *power))),
+                },
+            ]),
+            None => series,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::trig_quotients;
+    use crate::{
+        interpreter::{parse, run, Interpreter, InterpreterError},
+        node::Node,
+    };
+
+    fn assert_series(line: &str, a: f64, order: i32, expected: &[f64]) {
+        let interpreter = Interpreter::new();
+        let f = trig_quotients(&interpreter.visit(&parse(line), None).unwrap());
+        let (found, coefficients) = interpreter.series(&f, 'x', a, expected.len()).unwrap();
+        assert_eq!(found, order, "{line}");
+        for (c, expected) in coefficients.iter().zip(expected) {
+            assert!((c - expected).abs() < 1e-9, "{line}: {coefficients:?}");
+        }
+    }
+
+    #[test]
+    fn maclaurin_series() {
+        assert_series(
+            "e^x",
+            0f64,
+            0,
+            &[1f64, 1f64, 0.5, 1f64 / 6f64, 1f64 / 24f64],
+        );
+        assert_series("sin(x)", 0f64, 1, &[1f64, 0f64, -1f64 / 6f64]);
+    }
+
+    #[test]
+    fn taylor_series_about_a_point() {
+        assert_series("ln(x)", 1f64, 1, &[1f64, -0.5, 1f64 / 3f64, -0.25]);
+    }
+
+    #[test]
+    fn bernoulli_numbers() {
+        assert_series(
+            "x/(e^x-1)",
+            0f64,
+            0,
+            &[1f64, -0.5, 1f64 / 12f64, 0f64, -1f64 / 720f64],
+        );
+    }
+
+    #[test]
+    fn laurent_series() {
+        assert_series(
+            "csc(x)",
+            0f64,
+            -1,
+            &[1f64, 0f64, 1f64 / 6f64, 0f64, 7f64 / 360f64],
+        );
+        assert_series("cot(x)", 0f64, -1, &[1f64, 0f64, -1f64 / 3f64]);
+    }
+
+    #[test]
+    fn remainder_term() {
+        let Ok(Node::System(parts)) = run("taylor(e^x, x, 0, 2)") else {
+            panic!()
+        };
+        assert_eq!(parts[0].to_string(), "1+x+0.5*x^2");
+        assert_eq!(parts[1].to_string(), "R = 0.16666666666666666*x^3");
+    }
+
+    #[test]
+    fn essential_singularity_is_rejected() {
+        assert!(matches!(
+            run("taylor(e^(1/x), x, 0, 2)"),
+            Err(InterpreterError::SolveError(_))
+        ));
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use crate::{node::Node, token::CommandType};
+
+use super::{Interpreter, InterpreterError, InterpreterResult};
+
+impl Interpreter {
+    fn var_arg(
+        &self,
+        command: CommandType,
+        expr: &Node,
+        var: Option<&Node>,
+    ) -> InterpreterResult<char> {
+        match (var.map(Node::as_var), &self.unknowns(expr)[..]) {
+            (Some(Some(var)), _) | (None, &[var]) => Ok(var),
+            _ => Err(InterpreterError::InvalidArguments(command)),
+        }
+    }
+
+    fn at(&self, command: CommandType, expr: &Node, point: &[Node]) -> InterpreterResult<Node> {
+        let point = InterpreterResult::from_iter(point.iter().map(|arg| {
+            match arg {
+                Node::Equation { lhs, rhs } => Ok((
+                    lhs.as_var()
+                        .ok_or(InterpreterError::InvalidArguments(command))?,
+                    self.evaluate(rhs, &HashMap::new())?,
+                )),
+                _ => Err(InterpreterError::InvalidArguments(command)),
+            }
+        }))?;
+        self.visit(&self.visit(expr, None)?, Some(&point))
+    }
+
+    pub fn visit_command(
+        &self,
+        command: CommandType,
+        args: &[Node],
+        ext: Option<&HashMap<char, f64>>,
+    ) -> InterpreterResult<Node> {
+        Ok(match (command, args) {
+            (CommandType::Roots, [expr, var @ ..]) if var.len() <= 1 => Node::Vector(
+                self.roots(expr, self.var_arg(command, expr, var.first())?)?
+                    .into_iter()
+                    .map(|root| root.to_node())
+                    .collect(),
+            ),
+            (CommandType::Minimize, _) => self.optimize(args, false)?,
+            (CommandType::Maximize, _) => self.optimize(args, true)?,
+            (CommandType::Fit, _) => self.fit(args)?,
+            (
+                CommandType::Grad
+                | CommandType::Jacobian
+                | CommandType::Hessian
+                | CommandType::Div
+                | CommandType::Laplacian,
+                _,
+            ) => self.vector_calculus(command, args)?,
+            (CommandType::At, [expr, point @ ..]) => self.at(command, expr, point)?,
+            (CommandType::Deriv, [expr, var, point]) => self.at(
+                command,
+                &Node::Derivative {
+                    derivative: Box::new(expr.clone()),
+                    vars: vec![(
+                        var.as_var()
+                            .ok_or(InterpreterError::InvalidArguments(command))?,
+                        1,
+                    )],
+                },
+                &[Node::Equation {
+                    lhs: Box::new(var.clone()),
+                    rhs: Box::new(point.clone()),
+                }],
+            )?,
+            (CommandType::NDeriv, [expr, var, point]) => {
+                let var = var
+                    .as_var()
+                    .ok_or(InterpreterError::InvalidArguments(command))?;
+                let at = HashMap::from([(var, self.evaluate(point, &HashMap::new())?)]);
+                Node::Num(
+                    self.numeric_derivative(&self.visit(expr, None)?, var, &at)?
+                        .0,
+                )
+            }
+            (CommandType::Integrate, [expr, var @ ..]) if var.len() <= 1 => {
+                let expr = self.visit(expr, None)?;
+                self.indefinite_integral(&expr, self.var_arg(command, &expr, var.first())?)?
+            }
+            (CommandType::Integrate, [expr, var, a, b]) => {
+                let var = var
+                    .as_var()
+                    .ok_or(InterpreterError::InvalidArguments(command))?;
+                let mut ext = ext.cloned().unwrap_or_default();
+                let (a, b) = (self.evaluate(a, &ext)?, self.evaluate(b, &ext)?);
+                ext.remove(&var);
+                self.definite_integral(expr, var, a, b, &ext)?
+            }
+            (CommandType::Lim, [expr, approach]) => self.limit(expr, approach)?,
+            (CommandType::Taylor, _) => self.taylor(args)?,
+            (CommandType::Sum | CommandType::Prod, _) => self.summation(command, args)?,
+            (CommandType::Expand, [expr]) => self.expand(&self.visit(expr, None)?),
+            (CommandType::Factor, [expr, var @ ..]) if var.len() <= 1 => {
+                let expr = self.visit(expr, None)?;
+                let var = match (var, &self.unknowns(&expr)[..]) {
+                    ([], []) => 'x',
+                    _ => self.var_arg(command, &expr, var.first())?,
+                };
+                self.factor(&expr, var)?
+            }
+            _ => return Err(InterpreterError::InvalidArguments(command)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{interpreter::run, node::Node};
+
+    #[test]
+    fn bar_evaluates_any_expression() {
+        for (line, expected) in [
+            ("d/dx(x^3)|x=2", 12f64),
+            ("(x^2)|x=2", 4f64),
+            ("x|x=2", 2f64),
+            ("x^2 + 1|x=2", 5f64),
+            ("d/dx(x^3)|x=2 + 1", 13f64),
+            ("x*y|x=2|y=-3", -6f64),
+        ] {
+            assert_eq!(run(line).unwrap(), Node::Num(expected), "{line}");
+        }
+    }
+
+    #[test]
+    fn deriv_command() {
+        assert_eq!(run("deriv(x^3, x, 2)").unwrap(), Node::Num(12f64));
+    }
+}
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use crate::node::Node;
 
-use super::{Interpreter, InterpreterError, InterpreterResult};
+use super::{estimate, Interpreter, InterpreterError, InterpreterResult};
 
 impl Interpreter {
     pub fn visit_exponent(
@@ -14,6 +14,21 @@ impl Interpreter {
         Ok({
             let visited_base = self.visit(base, ext)?;
             let visited_exponent = self.visit(exponent, ext)?;
+            if let (Some((b, base_error)), Some((e, exponent_error))) =
+                (visited_base.as_estimate(), visited_exponent.as_estimate())
+            {
+                if base_error != 0f64 || exponent_error != 0f64 {
+                    let value = b.powf(e);
+                    let mut error = 0f64;
+                    if base_error != 0f64 {
+                        error += (e * b.powf(e - 1f64) * base_error).abs();
+                    }
+                    if exponent_error != 0f64 {
+                        error += (value * b.ln() * exponent_error).abs();
+                    }
+                    return Ok(estimate(value, error));
+                }
+            }
             if let Node::Num(base_num) = visited_base {
                 if let Node::Num(exponent_num) = visited_exponent {
                     if exponent_num == 0f64 {
@@ -23,7 +38,7 @@ impl Interpreter {
                             Node::Num(1f64)
                         }
                     } else if exponent_num == 1f64 {
-                        base.clone()
+                        Node::Num(base_num)
                     } else if base_num == 1f64 {
                         Node::Num(1f64)
                     } else {
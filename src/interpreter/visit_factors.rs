@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use crate::{node::Node, token::TokenType};
 
-use super::{Interpreter, InterpreterError, InterpreterResult};
+use super::{estimate, Interpreter, InterpreterError, InterpreterResult};
 
 impl Interpreter {
     pub fn visit_factors(
@@ -10,8 +10,12 @@ impl Interpreter {
         factors: &[(TokenType, Node)],
         ext: Option<&HashMap<char, f64>>,
     ) -> InterpreterResult<Node> {
+        if let [(TokenType::Mul, factor)] = factors {
+            return self.visit(factor, ext);
+        }
         Ok({
             let mut ans = 1f64;
+            let mut error = 0f64;
             let mut unresolved_factors = vec![];
             if factors
                 .iter()
@@ -19,23 +23,27 @@ impl Interpreter {
             {
                 return Ok(Node::Num(0f64));
             }
-            let mut visited_factors = InterpreterResult::<Vec<(TokenType, Node)>>::from_iter(
-                factors.iter().map(|(t, f)| Ok((*t, self.visit(f, ext)?))),
-            )?;
-            for (i, factor) in visited_factors.clone().iter().enumerate() {
-                if let (TokenType::Mul, Node::Factors(inner)) = factor {
-                    visited_factors.swap_remove(i);
-                    visited_factors.append(&mut inner.clone());
+            let mut visited_factors = vec![];
+            for (op, factor) in factors {
+                match (op, self.visit(factor, ext)?) {
+                    (TokenType::Mul, Node::Factors(mut inner)) => {
+                        visited_factors.append(&mut inner)
+                    }
+                    (op, factor) => visited_factors.push((*op, factor)),
                 }
             }
             for (op, factor) in visited_factors {
-                if let Node::Num(num) = factor {
+                if let Some((num, factor_error)) = factor.as_estimate() {
                     match op {
-                        TokenType::Mul => ans *= num,
+                        TokenType::Mul => {
+                            error = error * num.abs() + ans.abs() * factor_error;
+                            ans *= num;
+                        }
                         TokenType::Div => {
                             if num == 0f64 {
                                 return Err(InterpreterError::Undefined);
                             } else {
+                                error = (error + (ans / num).abs() * factor_error) / num.abs();
                                 ans /= num;
                             }
                         }
@@ -46,10 +54,10 @@ impl Interpreter {
                 }
             }
             if unresolved_factors.is_empty() {
-                Node::Num(ans)
+                estimate(ans, error)
             } else {
-                if ans != 1f64 {
-                    unresolved_factors.push((TokenType::Mul, Node::Num(ans)));
+                if ans != 1f64 || error != 0f64 {
+                    unresolved_factors.push((TokenType::Mul, estimate(ans, error)));
                 } else if unresolved_factors.len() == 1
                     && unresolved_factors.first().unwrap().0 == TokenType::Mul
                 {
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use crate::{node::Node, token::FuncType};
 
-use super::{is_int, Interpreter, InterpreterError, InterpreterResult};
+use super::{estimate, is_int, Interpreter, InterpreterError, InterpreterResult};
 
 impl Interpreter {
     pub fn visit_func(
@@ -13,6 +13,20 @@ impl Interpreter {
     ) -> InterpreterResult<Node> {
         Ok({
             let visited_arg = self.visit(arg, ext)?;
+            if let Node::Estimate { value, error } = visited_arg {
+                let at = |x: f64| -> InterpreterResult<f64> {
+                    self.visit_func(func, &Node::Num(x), ext)?
+                        .as_estimate()
+                        .map(|(y, _)| y)
+                        .ok_or(InterpreterError::Undefined)
+                };
+                let y = at(value)?;
+                let spread = [value - error, value + error]
+                    .into_iter()
+                    .filter_map(|x| at(x).ok())
+                    .fold(0f64, |spread, other| spread.max((other - y).abs()));
+                return Ok(estimate(y, spread));
+            }
             if let Node::Num(num) = visited_arg {
                 Node::Num(match func {
                     FuncType::Sin => num.sin(),
@@ -41,6 +55,13 @@ impl Interpreter {
                             num.log10()
                         }
                     }
+                    FuncType::Sqrt => {
+                        if num < 0f64 {
+                            return Err(InterpreterError::Undefined);
+                        } else {
+                            num.sqrt()
+                        }
+                    }
                 })
             } else {
                 Node::Func {
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use crate::{node::Node, token::TokenType};
 
-use super::{Interpreter, InterpreterResult};
+use super::{estimate, Interpreter, InterpreterResult};
 
 impl Interpreter {
     pub fn visit_terms(
@@ -12,9 +12,11 @@ impl Interpreter {
     ) -> InterpreterResult<Node> {
         Ok({
             let mut ans = 0f64;
+            let mut error = 0f64;
             let mut unresolved_terms = vec![];
             for (op, term) in terms.iter().map(|(o, t)| (o, self.visit(t, ext))) {
-                if let Node::Num(num) = term.clone()? {
+                if let Some((num, term_error)) = term.clone()?.as_estimate() {
+                    error += term_error;
                     match op {
                         TokenType::Plus => ans += num,
                         TokenType::Minus => ans -= num,
@@ -25,10 +27,10 @@ impl Interpreter {
                 }
             }
             if unresolved_terms.is_empty() {
-                Node::Num(ans)
+                estimate(ans, error)
             } else {
-                if ans != 0f64 {
-                    unresolved_terms.push((TokenType::Plus, Node::Num(ans)));
+                if ans != 0f64 || error != 0f64 {
+                    unresolved_terms.push((TokenType::Plus, estimate(ans, error)));
                 } else if unresolved_terms.len() == 1
                     && unresolved_terms.first().unwrap().0 == TokenType::Plus
                 {
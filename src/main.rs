@@ -1,6 +1,11 @@
 use std::io::{self, Write};
 
-use crate::{interpreter::Interpreter, parser::Parser, tokenizer::Tokenizer};
+use crate::{
+    interpreter::{Interpreter, OdeMethod, QuadratureMethod, SystemMethod},
+    node::Node,
+    parser::Parser,
+    tokenizer::Tokenizer,
+};
 
 mod interpreter;
 mod node;
@@ -8,105 +13,133 @@ mod parser;
 mod token;
 mod tokenizer;
 
-fn main() {
-    let mut line;
-    let mut tokenizer;
-    let mut parser;
-    let interpreter = Interpreter::new();
-    print!("Solve systems of two equations? (y/n) >");
+fn prompt(text: &str) -> String {
+    print!("{text}");
     io::stdout().flush().unwrap();
-    line = io::stdin().lines().next().unwrap().unwrap();
+    io::stdin().lines().next().unwrap().unwrap()
+}
+
+fn parse(line: &str) -> Result<Node, String> {
+    let tokens = Tokenizer::new(line).tokenize().map_err(|e| e.to_string())?;
+    Parser::new(&tokens).parse().map_err(|e| e.to_string())
+}
+
+fn read_system() -> Result<Vec<Node>, String> {
+    let mut eqs = vec![];
+    loop {
+        let line = prompt(if eqs.is_empty() { "┌" } else { "│" });
+        if line.trim().is_empty() {
+            return Ok(eqs);
+        }
+        eqs.push(parse(&line)?);
+    }
+}
+
+fn main() {
+    let mut interpreter = Interpreter::new();
+    let line = prompt("Solve systems of equations? (y/n) >");
     if line == "y" {
+        let method = if prompt("Use Broyden updates? (y/n) >") == "y" {
+            SystemMethod::Broyden
+        } else {
+            SystemMethod::Newton
+        };
         loop {
             println!("xcalcrs");
-            print!("┌");
-            io::stdout().flush().unwrap();
-            line = io::stdin().lines().next().unwrap().unwrap();
-            tokenizer = Tokenizer::new(&line);
-            let mut tokenizer_res = tokenizer.tokenize();
-            if let Err(err) = tokenizer_res {
-                println!("{err}");
-                continue;
-            }
-            let mut tokens = tokenizer_res.unwrap();
-            parser = Parser::new(&tokens);
-            let mut parser_res = parser.parse();
-            if let Err(err) = parser_res {
-                println!("{err}");
-                continue;
-            }
-            let mut node = parser_res.unwrap();
-            let mut interpreter_res = interpreter.visit(&node, None);
-            if let Err(err) = interpreter_res {
-                println!("{err}");
-                continue;
+            let eqs = match read_system() {
+                Ok(eqs) => eqs,
+                Err(err) => {
+                    println!("{err}");
+                    continue;
+                }
+            };
+            let mut vars = vec![];
+            for eq in &eqs {
+                vars.extend(interpreter.unknowns(eq));
             }
-            let eq1 = interpreter_res.unwrap();
-            print!("└");
-            io::stdout().flush().unwrap();
-            line = io::stdin().lines().next().unwrap().unwrap();
-            tokenizer = Tokenizer::new(&line);
-            tokenizer_res = tokenizer.tokenize();
-            if let Err(err) = tokenizer_res {
-                println!("{err}");
-                continue;
+            vars.sort_unstable();
+            vars.dedup();
+            match interpreter.solve_linear(&eqs, &vars) {
+                Ok(Some(solution)) => {
+                    println!("{solution}");
+                    continue;
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    println!("{err}");
+                    continue;
+                }
             }
-            tokens = tokenizer_res.unwrap();
-            parser = Parser::new(&tokens);
-            parser_res = parser.parse();
-            if let Err(err) = parser_res {
-                println!("{err}");
-                continue;
+            let mut guess = vec![];
+            for var in &vars {
+                match prompt(&format!("Guess {var} >")).trim().parse() {
+                    Ok(g) => guess.push(g),
+                    Err(err) => {
+                        println!("{err}");
+                        break;
+                    }
+                }
             }
-            node = parser_res.unwrap();
-            interpreter_res = interpreter.visit(&node, None);
-            if let Err(err) = interpreter_res {
-                println!("{err}");
+            if guess.len() != vars.len() {
                 continue;
             }
-            let eq2 = interpreter_res.unwrap();
-            print!("Guess x >");
-            io::stdout().flush().unwrap();
-            line = io::stdin().lines().next().unwrap().unwrap();
-            let mut guess = (0f64, 0f64);
-            guess.0 = line.parse().unwrap();
-            print!("Guess y >");
-            io::stdout().flush().unwrap();
-            line = io::stdin().lines().next().unwrap().unwrap();
-            guess.1 = line.parse().unwrap();
-            let system_res = interpreter.solve_system(&eq1, &eq2, guess);
-            if let Err(err) = system_res {
-                println!("{err}");
-                continue;
+            match interpreter.solve_system(&eqs, &vars, &guess, method) {
+                Ok(solution) => println!(
+                    "({}) = ({})",
+                    vars.iter()
+                        .map(char::to_string)
+                        .collect::<Vec<_>>()
+                        .join(","),
+                    solution
+                        .iter()
+                        .map(|v| format!("{v:.4}"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                Err(err) => println!("{err}"),
             }
-            let coords = system_res.unwrap();
-            println!("(x,y) = ({:.4}, {:.4})", coords.0, coords.1);
         }
     } else {
         loop {
-            print!("xcalcrs >");
-            io::stdout().flush().unwrap();
-            line = io::stdin().lines().next().unwrap().unwrap();
-            tokenizer = Tokenizer::new(&line);
-            let tokenizer_res = tokenizer.tokenize();
-            if let Err(err) = tokenizer_res {
-                println!("{err}");
+            let line = prompt("xcalcrs >");
+            if let Some(setting) = line.trim().strip_prefix(':') {
+                match setting.split_whitespace().collect::<Vec<_>>()[..] {
+                    ["complex", "on"] => interpreter.set_complex(true),
+                    ["complex", "off"] => interpreter.set_complex(false),
+                    ["ode", "rk4"] => interpreter.set_ode_method(OdeMethod::Rk4),
+                    ["ode", "dopri"] => interpreter.set_ode_method(OdeMethod::DormandPrince),
+                    ["quad", "gk"] => {
+                        interpreter.set_quadrature_method(QuadratureMethod::GaussKronrod)
+                    }
+                    ["quad", "simpson"] => {
+                        interpreter.set_quadrature_method(QuadratureMethod::Simpson)
+                    }
+                    ["quad", "romberg"] => {
+                        interpreter.set_quadrature_method(QuadratureMethod::Romberg)
+                    }
+                    ["check", "on"] => interpreter.set_check(true),
+                    ["check", "off"] => interpreter.set_check(false),
+                    ["steps", "on"] => interpreter.set_steps(true),
+                    ["steps", "off"] => interpreter.set_steps(false),
+                    _ => println!("unknown setting: {setting}"),
+                }
                 continue;
             }
-            let tokens = tokenizer_res.unwrap();
-            parser = Parser::new(&tokens);
-            let parser_res = parser.parse();
-            if let Err(err) = parser_res {
-                println!("{err}");
-                continue;
+            let node = match parse(&line) {
+                Ok(node) => node,
+                Err(err) => {
+                    println!("{err}");
+                    continue;
+                }
+            };
+            let res = interpreter.visit(&node, None);
+            for step in interpreter.take_steps() {
+                println!("{step}");
             }
-            let node = parser_res.unwrap();
-            let interpreter_res = interpreter.visit(&node, None);
-            if let Err(err) = interpreter_res {
-                println!("{err}");
-                continue;
+            match res {
+                Ok(res) => println!("{res}"),
+                Err(err) => println!("{err}"),
             }
-            println!("{}", interpreter_res.unwrap());
         }
     }
 }
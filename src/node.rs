@@ -1,11 +1,46 @@
 use std::fmt::{self, Write};
 
-use crate::token::{FuncType, TokenType};
+use crate::token::{CommandType, FuncType, TokenType};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub start: f64,
+    pub end: f64,
+    pub start_closed: bool,
+    pub end_closed: bool,
+}
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bound = |n: f64| {
+            if n == f64::INFINITY {
+                String::from("∞")
+            } else if n == f64::NEG_INFINITY {
+                String::from("-∞")
+            } else {
+                n.to_string()
+            }
+        };
+        if self.start == self.end {
+            write!(f, "{{{}}}", bound(self.start))
+        } else {
+            write!(
+                f,
+                "{}{}, {}{}",
+                if self.start_closed { '[' } else { '(' },
+                bound(self.start),
+                bound(self.end),
+                if self.end_closed { ']' } else { ')' },
+            )
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Node {
     Num(f64),
+    Bool(bool),
     Var(char),
+    Str(String),
     Func {
         func: FuncType,
         arg: Box<Self>,
@@ -18,37 +53,222 @@ pub enum Node {
     Terms(Vec<(TokenType, Self)>),
     Derivative {
         derivative: Box<Self>,
+        vars: Vec<(char, u32)>,
+    },
+    Prime {
         var: char,
+        order: u32,
+        wrt: Option<char>,
     },
     Equation {
         lhs: Box<Self>,
         rhs: Box<Self>,
     },
+    Relation {
+        lhs: Box<Self>,
+        op: TokenType,
+        rhs: Box<Self>,
+    },
+    Set {
+        var: char,
+        intervals: Vec<Interval>,
+    },
+    Range {
+        start: Box<Self>,
+        end: Box<Self>,
+    },
+    Approach {
+        var: char,
+        target: Box<Self>,
+        side: Option<TokenType>,
+    },
+    Vector(Vec<Self>),
+    System(Vec<Self>),
+    Table {
+        headers: Vec<char>,
+        rows: Vec<Vec<f64>>,
+    },
+    Command {
+        command: CommandType,
+        args: Vec<Self>,
+    },
+    Fit {
+        params: Vec<(char, f64, f64)>,
+        residuals: Vec<f64>,
+    },
+    Estimate {
+        value: f64,
+        error: f64,
+    },
+}
+impl Node {
+    pub fn as_var(&self) -> Option<char> {
+        match self {
+            Self::Var(var) => Some(*var),
+            Self::Factors(nodes) | Self::Terms(nodes) => match &nodes[..] {
+                [(TokenType::Mul | TokenType::Plus, node)] => node.as_var(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn as_estimate(&self) -> Option<(f64, f64)> {
+        match self {
+            Self::Num(value) => Some((*value, 0f64)),
+            Self::Estimate { value, error } => Some((*value, *error)),
+            _ => None,
+        }
+    }
+
+    pub fn replace(&self, f: &impl Fn(&Self) -> Option<Self>) -> Self {
+        if let Some(node) = f(self) {
+            return node;
+        }
+        let boxed = |node: &Self| Box::new(node.replace(f));
+        let pairs = |nodes: &[(TokenType, Self)]| {
+            nodes
+                .iter()
+                .map(|(op, node)| (*op, node.replace(f)))
+                .collect()
+        };
+        match self {
+            Self::Num(_)
+            | Self::Bool(_)
+            | Self::Var(_)
+            | Self::Str(_)
+            | Self::Prime { .. }
+            | Self::Set { .. }
+            | Self::Table { .. }
+            | Self::Fit { .. }
+            | Self::Estimate { .. } => self.clone(),
+            Self::Func { func, arg } => Self::Func {
+                func: func.clone(),
+                arg: boxed(arg),
+            },
+            Self::Exponent { base, exponent } => Self::Exponent {
+                base: boxed(base),
+                exponent: boxed(exponent),
+            },
+            Self::Factors(nodes) => Self::Factors(pairs(nodes)),
+            Self::Terms(nodes) => Self::Terms(pairs(nodes)),
+            Self::Derivative { derivative, vars } => Self::Derivative {
+                derivative: boxed(derivative),
+                vars: vars.clone(),
+            },
+            Self::Equation { lhs, rhs } => Self::Equation {
+                lhs: boxed(lhs),
+                rhs: boxed(rhs),
+            },
+            Self::Relation { lhs, op, rhs } => Self::Relation {
+                lhs: boxed(lhs),
+                op: *op,
+                rhs: boxed(rhs),
+            },
+            Self::Approach { var, target, side } => Self::Approach {
+                var: *var,
+                target: boxed(target),
+                side: *side,
+            },
+            Self::Range { start, end } => Self::Range {
+                start: boxed(start),
+                end: boxed(end),
+            },
+            Self::Vector(nodes) => Self::Vector(nodes.iter().map(|node| node.replace(f)).collect()),
+            Self::System(nodes) => Self::System(nodes.iter().map(|node| node.replace(f)).collect()),
+            Self::Command { command, args } => Self::Command {
+                command: *command,
+                args: args.iter().map(|node| node.replace(f)).collect(),
+            },
+        }
+    }
+
+    pub fn substitute(&self, var: char, value: &Self) -> Self {
+        self.replace(&|node| (node == &Self::Var(var)).then(|| value.clone()))
+    }
+
+    pub fn collect_vars(&self, vars: &mut Vec<char>) {
+        match self {
+            Self::Num(_)
+            | Self::Bool(_)
+            | Self::Str(_)
+            | Self::Table { .. }
+            | Self::Fit { .. }
+            | Self::Estimate { .. } => {}
+            Self::Set { var, .. } | Self::Prime { var, .. } => {
+                if !vars.contains(var) {
+                    vars.push(*var);
+                }
+            }
+            Self::Var(var) => {
+                if !vars.contains(var) {
+                    vars.push(*var);
+                }
+            }
+            Self::Func { arg, .. } => arg.collect_vars(vars),
+            Self::Exponent { base, exponent } => {
+                base.collect_vars(vars);
+                exponent.collect_vars(vars);
+            }
+            Self::Factors(nodes) | Self::Terms(nodes) => {
+                nodes.iter().for_each(|(_, node)| node.collect_vars(vars))
+            }
+            Self::Approach { var, target, .. } => {
+                if !vars.contains(var) {
+                    vars.push(*var);
+                }
+                target.collect_vars(vars);
+            }
+            Self::Derivative { derivative, .. } => derivative.collect_vars(vars),
+            Self::Equation { lhs, rhs }
+            | Self::Relation { lhs, rhs, .. }
+            | Self::Range {
+                start: lhs,
+                end: rhs,
+            } => {
+                lhs.collect_vars(vars);
+                rhs.collect_vars(vars);
+            }
+            Self::Vector(nodes) | Self::System(nodes) | Self::Command { args: nodes, .. } => {
+                nodes.iter().for_each(|node| node.collect_vars(vars))
+            }
+        }
+    }
 }
 impl fmt::Display for Node {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Num(n) => write!(f, "{n}"),
+            Self::Bool(b) => write!(f, "{b}"),
             Self::Var(c) => write!(f, "{c}"),
+            Self::Str(s) => write!(f, "\"{s}\""),
             Self::Func { func, arg } => write!(f, "{func}({arg})"),
             Self::Exponent { base, exponent } => {
-                match base.as_ref() {
-                    Node::Factors(_) | Node::Terms(_) | Node::Derivative { .. } => {
-                        write!(f, "({base})")?
-                    }
-                    _ => write!(f, "{base}")?,
+                let grouped = |node: &Node| match node {
+                    Node::Num(n) => *n < 0f64,
+                    Node::Factors(_)
+                    | Node::Terms(_)
+                    | Node::Exponent { .. }
+                    | Node::Derivative { .. } => true,
+                    _ => false,
+                };
+                if grouped(base) {
+                    write!(f, "({base})")?
+                } else {
+                    write!(f, "{base}")?
                 }
                 f.write_char('^')?;
-                match exponent.as_ref() {
-                    Node::Factors(_) | Node::Terms(_) | Node::Derivative { .. } => {
-                        write!(f, "({exponent})")
-                    }
-                    _ => write!(f, "{exponent}"),
+                if grouped(exponent) {
+                    write!(f, "({exponent})")
+                } else {
+                    write!(f, "{exponent}")
                 }
             }
             Self::Factors(factors) => {
                 for (i, factor) in factors.iter().enumerate() {
-                    if i > 0 {
+                    if i == 0 && factor.0 == TokenType::Div {
+                        f.write_str("1/")?;
+                    } else if i > 0 {
                         f.write_char(match factor.0 {
                             TokenType::Mul => '*',
                             TokenType::Div => '/',
@@ -56,6 +276,10 @@ impl fmt::Display for Node {
                         })?;
                     }
                     match &factor.1 {
+                        Node::Num(n) if i > 0 && *n < 0f64 => write!(f, "({n})")?,
+                        Node::Factors(_) if factor.0 == TokenType::Mul => {
+                            write!(f, "{}", factor.1)?
+                        }
                         Node::Factors(_) | Node::Terms(_) | Node::Derivative { .. } => {
                             write!(f, "({})", factor.1)?
                         }
@@ -65,25 +289,147 @@ impl fmt::Display for Node {
                 Ok(())
             }
             Self::Terms(terms) => {
-                for (i, term) in terms.iter().enumerate() {
-                    if i > 0 || term.0 == TokenType::Minus {
-                        f.write_char(match term.0 {
-                            TokenType::Plus => '+',
-                            TokenType::Minus => '-',
-                            _ => unreachable!(),
-                        })?;
-                    }
-                    match &term.1 {
-                        Node::Factors(_) | Node::Terms(_) | Node::Derivative { .. } => {
-                            write!(f, "({})", term.1)?
+                for (i, (op, term)) in terms.iter().enumerate() {
+                    let (negative, term) = match term {
+                        Node::Num(n) if i > 0 && *n < 0f64 => {
+                            (*op == TokenType::Plus, &Node::Num(-n))
                         }
+                        term => (*op == TokenType::Minus, term),
+                    };
+                    if negative {
+                        f.write_char('-')?;
+                    } else if i > 0 {
+                        f.write_char('+')?;
+                    }
+                    match term {
+                        Node::Num(n) if negative && *n < 0f64 => write!(f, "({n})")?,
+                        Node::Terms(_) | Node::Derivative { .. } => write!(f, "({term})")?,
                         e => write!(f, "{e}")?,
                     }
                 }
                 Ok(())
             }
-            Self::Derivative { derivative, var } => write!(f, "d/d{var}[{derivative}]"),
+            Self::Derivative { derivative, vars } => {
+                let d = if vars.len() > 1 { '∂' } else { 'd' };
+                match vars.iter().map(|(_, order)| order).sum::<u32>() {
+                    1 => write!(f, "{d}/")?,
+                    order => write!(f, "{d}^{order}/")?,
+                }
+                for (var, order) in vars {
+                    match order {
+                        1 => write!(f, "{d}{var}")?,
+                        order => write!(f, "{d}{var}^{order}")?,
+                    }
+                }
+                write!(f, "[{derivative}]")
+            }
+            Self::Prime { var, order, wrt } => {
+                write!(f, "{var}{}", "'".repeat(*order as usize))?;
+                match wrt {
+                    Some(wrt) => write!(f, "({wrt})"),
+                    None => Ok(()),
+                }
+            }
             Self::Equation { lhs, rhs } => write!(f, "{lhs} = {rhs}"),
+            Self::Relation { lhs, op, rhs } => write!(
+                f,
+                "{lhs} {} {rhs}",
+                match op {
+                    TokenType::Less => "<",
+                    TokenType::LessEqual => "<=",
+                    TokenType::Greater => ">",
+                    TokenType::GreaterEqual => ">=",
+                    TokenType::NotEqual => "!=",
+                    _ => unreachable!(),
+                }
+            ),
+            Self::Range { start, end } => write!(f, "{start}..{end}"),
+            Self::Approach { var, target, side } => {
+                write!(f, "{var} -> {target}")?;
+                match side {
+                    Some(TokenType::Plus) => f.write_char('+'),
+                    Some(TokenType::Minus) => f.write_char('-'),
+                    _ => Ok(()),
+                }
+            }
+            Self::Set { var, intervals } => {
+                if intervals.is_empty() {
+                    return write!(f, "{var} ∈ ∅");
+                }
+                write!(f, "{var} ∈ ")?;
+                for (i, interval) in intervals.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" ∪ ")?;
+                    }
+                    write!(f, "{interval}")?;
+                }
+                Ok(())
+            }
+            Self::Vector(nodes) => {
+                f.write_char('[')?;
+                for (i, node) in nodes.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{node}")?;
+                }
+                f.write_char(']')
+            }
+            Self::System(nodes) => {
+                for (i, node) in nodes.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{node}")?;
+                }
+                Ok(())
+            }
+            Self::Table { headers, rows } => {
+                for (i, header) in headers.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(' ')?;
+                    }
+                    write!(f, "{header:<20}")?;
+                }
+                for row in rows {
+                    f.write_char('\n')?;
+                    for (i, value) in row.iter().enumerate() {
+                        if i > 0 {
+                            f.write_char(' ')?;
+                        }
+                        write!(f, "{value:<20}")?;
+                    }
+                }
+                Ok(())
+            }
+            Self::Fit { params, residuals } => {
+                for (i, (param, value, error)) in params.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{param} = {value} ± {error}")?;
+                }
+                f.write_str("\nresiduals: [")?;
+                for (i, residual) in residuals.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{residual}")?;
+                }
+                f.write_char(']')
+            }
+            Self::Estimate { value, error: 0f64 } => write!(f, "{value}"),
+            Self::Estimate { value, error } => write!(f, "{value} ± {error:.1e}"),
+            Self::Command { command, args } => {
+                write!(f, "{command}(")?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{arg}")?;
+                }
+                f.write_char(')')
+            }
         }
     }
 }
@@ -2,14 +2,13 @@ use std::fmt;
 
 use crate::{
     node::Node,
-    token::{Token, TokenType},
+    token::{CommandType, Token, TokenType},
 };
 
 #[derive(Debug)]
 pub enum ParserError {
     Expected { expected: TokenType, got: TokenType },
     Unexpected(TokenType),
-    DifferentialEquation,
 }
 impl fmt::Display for ParserError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -18,20 +17,26 @@ impl fmt::Display for ParserError {
                 f.write_fmt(format_args!("expected {expected:?}, got {got:?}"))
             }
             Self::Unexpected(unexpected) => f.write_fmt(format_args!("unexpected {unexpected:?}")),
-            Self::DifferentialEquation => f.write_str("differential equations not supported yet"),
         }
     }
 }
 
 pub type ParserResult<T> = Result<T, ParserError>;
 
+type Operator = (Option<char>, Vec<(char, u32)>);
+
 pub struct Parser<'a> {
     tokens: &'a [Token],
     curr: usize,
+    integrals: usize,
 }
 impl<'a> Parser<'a> {
     pub fn new(tokens: &'a [Token]) -> Self {
-        Self { tokens, curr: 0 }
+        Self {
+            tokens,
+            curr: 0,
+            integrals: 0,
+        }
     }
 
     fn rest(&self) -> &'a [Token] {
@@ -54,12 +59,6 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn retract_n(&mut self, n: usize) {
-        for _ in 0..n {
-            self.retract();
-        }
-    }
-
     fn accept(&mut self, token_type: &TokenType) -> Option<Token> {
         let token = self.peek()?;
         if &token == token_type {
@@ -101,16 +100,148 @@ impl<'a> Parser<'a> {
         }
     }
 
+    fn parse_command(&mut self) -> ParserResult<Node> {
+        if let Token::Command(command) = self.expect(&TokenType::Command)? {
+            self.expect(&TokenType::LParen)?;
+            let mut args = vec![self.parse_arg()?];
+            while self.accept(&TokenType::Comma).is_some() {
+                args.push(self.parse_arg()?);
+            }
+            self.expect(&TokenType::RParen)?;
+            Ok(Node::Command { command, args })
+        } else {
+            unreachable!()
+        }
+    }
+
+    fn parse_relation(&mut self, lhs: Node) -> ParserResult<Node> {
+        match self.peek().map(TokenType::from) {
+            Some(op) if op.is_relation() => {
+                self.advance();
+                Ok(Node::Relation {
+                    lhs: Box::new(lhs),
+                    op,
+                    rhs: Box::new(self.parse_expr()?),
+                })
+            }
+            _ => Ok(lhs),
+        }
+    }
+
+    fn parse_arg(&mut self) -> ParserResult<Node> {
+        if let Some(Token::Str(text)) = self.accept(&TokenType::Str) {
+            return Ok(Node::Str(text));
+        }
+        let node = self.parse_expr()?;
+        if self.accept(&TokenType::Range).is_some() {
+            Ok(Node::Range {
+                start: Box::new(node),
+                end: Box::new(self.parse_expr()?),
+            })
+        } else if self.accept(&TokenType::Equals).is_some() {
+            Ok(Node::Equation {
+                lhs: Box::new(node),
+                rhs: Box::new(self.parse_expr()?),
+            })
+        } else if self.accept(&TokenType::Arrow).is_some() {
+            let var = node
+                .as_var()
+                .ok_or(ParserError::Unexpected(TokenType::Arrow))?;
+            let target = Box::new(self.parse_expr()?);
+            let side = match self.peek() {
+                Some(Token::Plus) => Some(TokenType::Plus),
+                Some(Token::Minus) => Some(TokenType::Minus),
+                _ => None,
+            };
+            if side.is_some() {
+                self.advance();
+            }
+            Ok(Node::Approach { var, target, side })
+        } else {
+            self.parse_relation(node)
+        }
+    }
+
+    fn at_trailing_sign(&self) -> bool {
+        matches!(
+            self.rest(),
+            [
+                Token::Plus | Token::Minus,
+                Token::RParen | Token::Comma | Token::Eof,
+                ..
+            ]
+        )
+    }
+
     fn parse_atom(&mut self) -> ParserResult<Node> {
-        if let Some(Token::Var(var)) = self.accept(&TokenType::Var) {
-            Ok(Node::Var(var))
+        if let Some(derivative) = self.parse_derivative()? {
+            Ok(derivative)
+        } else if let Some(Token::Var(var)) = self.accept(&TokenType::Var) {
+            let mut order = 0;
+            while self.accept(&TokenType::Prime).is_some() {
+                order += 1;
+            }
+            if order == 0 {
+                return Ok(Node::Var(var));
+            }
+            let prime = Node::Prime {
+                var,
+                order,
+                wrt: None,
+            };
+            if self.accept(&TokenType::LParen).is_none() {
+                return Ok(prime);
+            }
+            let at = self.parse_expr()?;
+            self.expect(&TokenType::RParen)?;
+            Ok(match at.as_var() {
+                Some(wrt) => Node::Prime {
+                    var,
+                    order,
+                    wrt: Some(wrt),
+                },
+                None => Node::Factors(vec![(TokenType::Mul, prime), (TokenType::Mul, at)]),
+            })
         } else if self.accept(&TokenType::LParen).is_some() {
             let node = self.parse_expr()?;
+            if self.accept(&TokenType::Comma).is_some() {
+                let mut nodes = vec![node, self.parse_expr()?];
+                while self.accept(&TokenType::Comma).is_some() {
+                    nodes.push(self.parse_expr()?);
+                }
+                self.expect(&TokenType::RParen)?;
+                return Ok(Node::Vector(nodes));
+            }
             self.expect(&TokenType::RParen)?;
             Ok(node)
+        } else if self.accept(&TokenType::Integral).is_some() {
+            self.integrals += 1;
+            let integrand = self.parse_expr();
+            self.integrals -= 1;
+            let integrand = integrand?;
+            if !self.accept_token(&Token::Var('d')) {
+                return Err(ParserError::Unexpected(self.peek().unwrap().into()));
+            }
+            let Token::Var(var) = self.expect(&TokenType::Var)? else {
+                unreachable!()
+            };
+            Ok(Node::Command {
+                command: CommandType::Integrate,
+                args: vec![integrand, Node::Var(var)],
+            })
+        } else if self.accept(&TokenType::LBracket).is_some() {
+            let mut nodes = vec![self.parse_expr()?];
+            while self.accept(&TokenType::Comma).is_some() {
+                nodes.push(self.parse_expr()?);
+            }
+            self.expect(&TokenType::RBracket)?;
+            Ok(Node::Vector(nodes))
         } else if self.accept(&TokenType::Func).is_some() {
             self.retract();
             self.parse_func()
+        } else if self.accept(&TokenType::Command).is_some() {
+            self.retract();
+            self.parse_command()
         } else {
             Err(ParserError::Unexpected(self.peek().unwrap().into()))
         }
@@ -139,12 +270,15 @@ impl<'a> Parser<'a> {
     fn parse_factors(&mut self) -> ParserResult<Node> {
         let mut factors = vec![];
         factors.push((TokenType::Mul, self.parse_factor()?));
-        while self.accept(&TokenType::Var).is_some()
+        while !self.at_differential() && self.accept(&TokenType::Var).is_some()
+            || self.accept(&TokenType::Partial).is_some()
+            || self.accept(&TokenType::Integral).is_some()
             || self.accept(&TokenType::Func).is_some()
+            || self.accept(&TokenType::Command).is_some()
             || self.accept(&TokenType::LParen).is_some()
         {
             self.retract();
-            factors.push((TokenType::Mul, self.parse_atom()?));
+            factors.push((TokenType::Mul, self.parse_factor()?));
         }
         Ok(Node::Factors(factors))
     }
@@ -176,10 +310,24 @@ impl<'a> Parser<'a> {
             .map_or(TokenType::Plus, |_| TokenType::Minus);
         terms.push((sign, self.parse_term()?));
         loop {
-            if self.accept(&TokenType::Plus).is_some() {
+            if self.at_trailing_sign() {
+                break;
+            } else if self.accept(&TokenType::Plus).is_some() {
                 terms.push((TokenType::Plus, self.parse_term()?));
             } else if self.accept(&TokenType::Minus).is_some() {
                 terms.push((TokenType::Minus, self.parse_term()?));
+            } else if self.accept(&TokenType::Bar).is_some() {
+                let mut args = vec![Node::Terms(std::mem::take(&mut terms)), self.parse_point()?];
+                while self.accept(&TokenType::Bar).is_some() {
+                    args.push(self.parse_point()?);
+                }
+                terms.push((
+                    TokenType::Plus,
+                    Node::Command {
+                        command: CommandType::At,
+                        args,
+                    },
+                ));
             } else {
                 break;
             }
@@ -187,41 +335,135 @@ impl<'a> Parser<'a> {
         Ok(Node::Terms(terms))
     }
 
-    fn parse_derivative(&mut self) -> ParserResult<Node> {
-        if self.accept_token(&Token::Var('d')) {
-            if self.accept(&TokenType::Div).is_some() {
-                if self.accept_token(&Token::Var('d')) {
-                    if let Some(Token::Var(var)) = self.accept(&TokenType::Var) {
-                        self.expect(&TokenType::LParen)?;
-                        let derivative = Box::new(self.parse_derivative()?);
-                        self.expect(&TokenType::RParen)?;
-                        return Ok(Node::Derivative { derivative, var });
-                    } else {
-                        self.retract_n(3);
-                    }
-                } else {
-                    self.retract_n(2);
-                }
-            } else {
-                self.retract();
+    fn parse_point(&mut self) -> ParserResult<Node> {
+        let Token::Var(var) = self.expect(&TokenType::Var)? else {
+            unreachable!()
+        };
+        self.expect(&TokenType::Equals)?;
+        let value = match self.accept(&TokenType::Minus) {
+            Some(_) => Node::Terms(vec![(TokenType::Minus, self.parse_factor()?)]),
+            None => self.parse_factor()?,
+        };
+        Ok(Node::Equation {
+            lhs: Box::new(Node::Var(var)),
+            rhs: Box::new(value),
+        })
+    }
+
+    fn at_differential(&self) -> bool {
+        self.integrals > 0 && matches!(self.rest(), [Token::Var('d'), Token::Var(_), ..])
+    }
+
+    fn accept_differential(&mut self) -> bool {
+        self.accept_token(&Token::Var('d')) || self.accept(&TokenType::Partial).is_some()
+    }
+
+    fn accept_order(&mut self) -> Option<u32> {
+        let order = if self.accept(&TokenType::Raise).is_some() {
+            self.accept(&TokenType::Num)?
+        } else {
+            match self.accept(&TokenType::Num) {
+                Some(order) => order,
+                None => return Some(1),
             }
+        };
+        match order {
+            Token::Num(n) if n >= 1f64 && n.fract() == 0f64 => Some(n as u32),
+            _ => None,
         }
-        self.parse_expr()
     }
 
-    pub fn parse(&mut self) -> ParserResult<Node> {
-        let mut node = self.parse_derivative()?;
-        if self.accept(&TokenType::Equals).is_some() {
-            if let Node::Derivative { .. } = node {
-                return Err(ParserError::DifferentialEquation);
-            } else {
-                node = Node::Equation {
-                    lhs: Box::new(node),
-                    rhs: Box::new(self.parse_expr()?),
+    fn parse_operator(&mut self) -> Option<Operator> {
+        if !self.accept_differential() {
+            return None;
+        }
+        let order = self.accept_order()?;
+        let dependent = match self.accept(&TokenType::Var) {
+            Some(Token::Var(dependent)) => Some(dependent),
+            _ => None,
+        };
+        self.accept(&TokenType::Div)?;
+        let mut vars = vec![];
+        while self.accept_differential() {
+            let Some(Token::Var(var)) = self.accept(&TokenType::Var) else {
+                return None;
+            };
+            vars.push((var, self.accept_order()?));
+        }
+        (!vars.is_empty() && vars.iter().map(|(_, order)| order).sum::<u32>() == order)
+            .then_some((dependent, vars))
+    }
+
+    fn parse_derivative(&mut self) -> ParserResult<Option<Node>> {
+        let start = self.curr;
+        let Some((dependent, vars)) = self.parse_operator() else {
+            self.curr = start;
+            return Ok(None);
+        };
+        match dependent {
+            Some(dependent) => {
+                let derivative = Node::Derivative {
+                    derivative: Box::new(Node::Var(dependent)),
+                    vars,
                 };
+                if self.accept(&TokenType::LParen).is_some() {
+                    let at = self.parse_expr()?;
+                    self.expect(&TokenType::RParen)?;
+                    return Ok(Some(Node::Factors(vec![
+                        (TokenType::Mul, derivative),
+                        (TokenType::Mul, at),
+                    ])));
+                }
+                Ok(Some(derivative))
+            }
+            None => {
+                if self.accept(&TokenType::LParen).is_none() {
+                    self.curr = start;
+                    return Ok(None);
+                }
+                let mut derivative = Box::new(self.parse_expr()?);
+                if self.accept(&TokenType::Equals).is_some() {
+                    derivative = Box::new(Node::Equation {
+                        lhs: derivative,
+                        rhs: Box::new(self.parse_expr()?),
+                    });
+                }
+                self.expect(&TokenType::RParen)?;
+                Ok(Some(Node::Derivative { derivative, vars }))
             }
         }
+    }
+
+    fn parse_statement(&mut self) -> ParserResult<Node> {
+        let node = self.parse_expr()?;
+        if self.accept(&TokenType::Equals).is_some() {
+            let rhs = self.parse_expr()?;
+            Ok(Node::Equation {
+                lhs: Box::new(node),
+                rhs: Box::new(if self.accept(&TokenType::Range).is_some() {
+                    Node::Range {
+                        start: Box::new(rhs),
+                        end: Box::new(self.parse_expr()?),
+                    }
+                } else {
+                    rhs
+                }),
+            })
+        } else {
+            self.parse_relation(node)
+        }
+    }
+
+    pub fn parse(&mut self) -> ParserResult<Node> {
+        let mut statements = vec![self.parse_statement()?];
+        while self.accept(&TokenType::Comma).is_some() {
+            statements.push(self.parse_statement()?);
+        }
         self.expect(&TokenType::Eof)?;
-        Ok(node)
+        Ok(if statements.len() == 1 {
+            statements.remove(0)
+        } else {
+            Node::System(statements)
+        })
     }
 }
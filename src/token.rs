@@ -10,6 +10,7 @@ pub enum FuncType {
     Cot,
     Ln,
     Log,
+    Sqrt,
 }
 impl fmt::Display for FuncType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -22,6 +23,55 @@ impl fmt::Display for FuncType {
             Self::Cot => "cot",
             Self::Ln => "ln",
             Self::Log => "log",
+            Self::Sqrt => "sqrt",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandType {
+    Roots,
+    Minimize,
+    Maximize,
+    Fit,
+    Expand,
+    Factor,
+    Grad,
+    Jacobian,
+    Hessian,
+    Div,
+    Laplacian,
+    Deriv,
+    NDeriv,
+    Integrate,
+    Lim,
+    Taylor,
+    Sum,
+    Prod,
+    At,
+}
+impl fmt::Display for CommandType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Roots => "roots",
+            Self::Minimize => "minimize",
+            Self::Maximize => "maximize",
+            Self::Fit => "fit",
+            Self::Expand => "expand",
+            Self::Factor => "factor",
+            Self::Grad => "grad",
+            Self::Jacobian => "jacobian",
+            Self::Hessian => "hessian",
+            Self::Div => "div",
+            Self::Laplacian => "laplacian",
+            Self::Deriv => "deriv",
+            Self::NDeriv => "nderiv",
+            Self::Integrate => "integrate",
+            Self::Lim => "lim",
+            Self::Taylor => "taylor",
+            Self::Sum => "sum",
+            Self::Prod => "prod",
+            Self::At => "at",
         })
     }
 }
@@ -31,15 +81,31 @@ pub enum Token {
     Eof,
     Num(f64),
     Var(char),
+    Str(String),
     Func(FuncType),
+    Command(CommandType),
     LParen,
     RParen,
+    LBracket,
+    RBracket,
     Plus,
     Minus,
     Mul,
     Div,
     Raise,
     Equals,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    NotEqual,
+    Comma,
+    Range,
+    Arrow,
+    Prime,
+    Partial,
+    Integral,
+    Bar,
 }
 impl PartialEq<TokenType> for Token {
     fn eq(&self, other: &TokenType) -> bool {
@@ -52,15 +118,39 @@ pub enum TokenType {
     Eof,
     Num,
     Var,
+    Str,
     Func,
+    Command,
     LParen,
     RParen,
+    LBracket,
+    RBracket,
     Plus,
     Minus,
     Mul,
     Div,
     Raise,
     Equals,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    NotEqual,
+    Comma,
+    Range,
+    Arrow,
+    Prime,
+    Partial,
+    Integral,
+    Bar,
+}
+impl TokenType {
+    pub fn is_relation(self) -> bool {
+        matches!(
+            self,
+            Self::Less | Self::LessEqual | Self::Greater | Self::GreaterEqual | Self::NotEqual
+        )
+    }
 }
 impl From<Token> for TokenType {
     fn from(value: Token) -> Self {
@@ -68,15 +158,31 @@ impl From<Token> for TokenType {
             Token::Eof => Self::Eof,
             Token::Num(_) => Self::Num,
             Token::Var(_) => Self::Var,
+            Token::Str(_) => Self::Str,
             Token::Func(_) => Self::Func,
+            Token::Command(_) => Self::Command,
             Token::LParen => Self::LParen,
             Token::RParen => Self::RParen,
+            Token::LBracket => Self::LBracket,
+            Token::RBracket => Self::RBracket,
             Token::Plus => Self::Plus,
             Token::Minus => Self::Minus,
             Token::Mul => Self::Mul,
             Token::Div => Self::Div,
             Token::Raise => Self::Raise,
             Token::Equals => Self::Equals,
+            Token::Less => Self::Less,
+            Token::LessEqual => Self::LessEqual,
+            Token::Greater => Self::Greater,
+            Token::GreaterEqual => Self::GreaterEqual,
+            Token::NotEqual => Self::NotEqual,
+            Token::Comma => Self::Comma,
+            Token::Range => Self::Range,
+            Token::Arrow => Self::Arrow,
+            Token::Prime => Self::Prime,
+            Token::Partial => Self::Partial,
+            Token::Integral => Self::Integral,
+            Token::Bar => Self::Bar,
         }
     }
 }
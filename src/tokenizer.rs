@@ -1,15 +1,17 @@
 use std::fmt;
 
-use crate::token::{FuncType, Token};
+use crate::token::{CommandType, FuncType, Token};
 
 #[derive(Debug)]
 pub enum TokenizerError {
     IllegalChar(char),
+    UnterminatedString,
 }
 impl fmt::Display for TokenizerError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::IllegalChar(ch) => f.write_fmt(format_args!("illegal character: {ch}")),
+            Self::UnterminatedString => f.write_str("unterminated string"),
         }
     }
 }
@@ -45,6 +47,12 @@ impl<'a> Tokenizer<'a> {
         }
     }
 
+    fn at_command(&self, keyword: &str) -> bool {
+        self.rest()
+            .strip_prefix(keyword)
+            .is_some_and(|rest| rest.trim_start().starts_with('('))
+    }
+
     fn take_while<P>(&mut self, mut pred: P) -> Option<&'a str>
     where
         P: FnMut(char) -> bool,
@@ -71,18 +79,22 @@ impl<'a> Tokenizer<'a> {
                 s if s.is_whitespace() => self.advance(),
                 '0'..='9' => {
                     let mut point = false;
-                    tokens.push(Token::Num(
-                        self.take_while(|c| match c {
-                            '.' if point => {
+                    let mut literal = self
+                        .take_while(|c| match c {
+                            '.' if !point => {
                                 point = true;
                                 true
                             }
                             '0'..='9' => true,
                             _ => false,
                         })
-                        .expect("one digit already seen")
-                        .parse()
-                        .expect("parsed string should be a number"),
+                        .expect("one digit already seen");
+                    if literal.ends_with('.') && self.peek() == Some('.') {
+                        self.curr -= 1;
+                        literal = &literal[..literal.len() - 1];
+                    }
+                    tokens.push(Token::Num(
+                        literal.parse().expect("parsed string should be a number"),
                     ));
                 }
                 c if c.is_alphabetic() => {
@@ -111,6 +123,63 @@ impl<'a> Tokenizer<'a> {
                     } else if rest.starts_with("log") {
                         self.advance_n(3);
                         tokens.push(Token::Func(FuncType::Log));
+                    } else if rest.starts_with("sqrt") {
+                        self.advance_n(4);
+                        tokens.push(Token::Func(FuncType::Sqrt));
+                    } else if self.at_command("minimize") {
+                        self.advance_n(8);
+                        tokens.push(Token::Command(CommandType::Minimize));
+                    } else if self.at_command("maximize") {
+                        self.advance_n(8);
+                        tokens.push(Token::Command(CommandType::Maximize));
+                    } else if self.at_command("fit") {
+                        self.advance_n(3);
+                        tokens.push(Token::Command(CommandType::Fit));
+                    } else if self.at_command("expand") {
+                        self.advance_n(6);
+                        tokens.push(Token::Command(CommandType::Expand));
+                    } else if self.at_command("factor") {
+                        self.advance_n(6);
+                        tokens.push(Token::Command(CommandType::Factor));
+                    } else if self.at_command("grad") {
+                        self.advance_n(4);
+                        tokens.push(Token::Command(CommandType::Grad));
+                    } else if self.at_command("jacobian") {
+                        self.advance_n(8);
+                        tokens.push(Token::Command(CommandType::Jacobian));
+                    } else if self.at_command("hessian") {
+                        self.advance_n(7);
+                        tokens.push(Token::Command(CommandType::Hessian));
+                    } else if self.at_command("nderiv") {
+                        self.advance_n(6);
+                        tokens.push(Token::Command(CommandType::NDeriv));
+                    } else if self.at_command("deriv") {
+                        self.advance_n(5);
+                        tokens.push(Token::Command(CommandType::Deriv));
+                    } else if self.at_command("integrate") {
+                        self.advance_n(9);
+                        tokens.push(Token::Command(CommandType::Integrate));
+                    } else if self.at_command("div") {
+                        self.advance_n(3);
+                        tokens.push(Token::Command(CommandType::Div));
+                    } else if self.at_command("lim") {
+                        self.advance_n(3);
+                        tokens.push(Token::Command(CommandType::Lim));
+                    } else if self.at_command("laplacian") {
+                        self.advance_n(9);
+                        tokens.push(Token::Command(CommandType::Laplacian));
+                    } else if self.at_command("taylor") {
+                        self.advance_n(6);
+                        tokens.push(Token::Command(CommandType::Taylor));
+                    } else if self.at_command("sum") {
+                        self.advance_n(3);
+                        tokens.push(Token::Command(CommandType::Sum));
+                    } else if self.at_command("prod") {
+                        self.advance_n(4);
+                        tokens.push(Token::Command(CommandType::Prod));
+                    } else if self.at_command("roots") {
+                        self.advance_n(5);
+                        tokens.push(Token::Command(CommandType::Roots));
                     } else {
                         self.advance();
                         tokens.push(Token::Var(c));
@@ -120,6 +189,10 @@ impl<'a> Tokenizer<'a> {
                     self.advance();
                     tokens.push(Token::Plus);
                 }
+                '-' if self.rest().starts_with("->") => {
+                    self.advance_n(2);
+                    tokens.push(Token::Arrow);
+                }
                 '-' => {
                     self.advance();
                     tokens.push(Token::Minus);
@@ -144,10 +217,70 @@ impl<'a> Tokenizer<'a> {
                     self.advance();
                     tokens.push(Token::RParen);
                 }
+                '[' => {
+                    self.advance();
+                    tokens.push(Token::LBracket);
+                }
+                ']' => {
+                    self.advance();
+                    tokens.push(Token::RBracket);
+                }
                 '=' => {
                     self.advance();
                     tokens.push(Token::Equals);
                 }
+                '.' if self.rest().starts_with("..") => {
+                    self.advance_n(2);
+                    tokens.push(Token::Range);
+                }
+                '<' | '>' | '!' => {
+                    self.advance();
+                    let equals = self.peek() == Some('=');
+                    if equals {
+                        self.advance();
+                    }
+                    tokens.push(match (ch, equals) {
+                        ('<', false) => Token::Less,
+                        ('<', true) => Token::LessEqual,
+                        ('>', false) => Token::Greater,
+                        ('>', true) => Token::GreaterEqual,
+                        ('!', true) => Token::NotEqual,
+                        _ => return Err(TokenizerError::IllegalChar(ch)),
+                    });
+                }
+                ',' => {
+                    self.advance();
+                    tokens.push(Token::Comma);
+                }
+                '\'' => {
+                    self.advance();
+                    tokens.push(Token::Prime);
+                }
+                '|' => {
+                    self.advance();
+                    tokens.push(Token::Bar);
+                }
+                '∂' => {
+                    self.advance();
+                    tokens.push(Token::Partial);
+                }
+                '∞' => {
+                    self.advance();
+                    tokens.push(Token::Num(f64::INFINITY));
+                }
+                '∫' => {
+                    self.advance();
+                    tokens.push(Token::Integral);
+                }
+                '"' => {
+                    self.advance();
+                    let text = self.take_while(|c| c != '"').unwrap_or_default();
+                    if self.peek() != Some('"') {
+                        return Err(TokenizerError::UnterminatedString);
+                    }
+                    self.advance();
+                    tokens.push(Token::Str(String::from(text)));
+                }
                 o => return Err(TokenizerError::IllegalChar(o)),
             }
         }
@@ -155,3 +288,41 @@ impl<'a> Tokenizer<'a> {
         Ok(tokens)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Tokenizer;
+    use crate::token::{CommandType, Token};
+
+    fn tokenize(text: &str) -> Vec<Token> {
+        Tokenizer::new(text).tokenize().unwrap()
+    }
+
+    #[test]
+    fn keyword_without_parenthesis_is_a_product_of_variables() {
+        assert_eq!(
+            tokenize("sum"),
+            [
+                Token::Var('s'),
+                Token::Var('u'),
+                Token::Var('m'),
+                Token::Eof
+            ]
+        );
+        assert_eq!(
+            tokenize("div")[..3],
+            [Token::Var('d'), Token::Var('i'), Token::Var('v')]
+        );
+    }
+
+    #[test]
+    fn keyword_before_parenthesis_is_a_command() {
+        assert_eq!(tokenize("sum(")[0], Token::Command(CommandType::Sum));
+        assert_eq!(tokenize("lim (")[0], Token::Command(CommandType::Lim));
+    }
+
+    #[test]
+    fn decimal_literal() {
+        assert_eq!(tokenize("1.25"), [Token::Num(1.25), Token::Eof]);
+    }
+}